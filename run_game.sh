@@ -8,6 +8,13 @@ cargo build
 # ./halite --replay-directory replays/ -vvv -s 1548006861 --width 64 --height 64 "RUST_BACKTRACE=1 ./target/debug/my_bot" "RUST_BACKTRACE=1 ./target/debug/v15"
 # ./halite --replay-directory replays/ -vvv -s 1548121013 --width 64 --height 64 "RUST_BACKTRACE=1 ./target/debug/my_bot" "RUST_BACKTRACE=1 ./target/debug/v18"
 # ./halite --replay-directory replays/ -vvv -s 1548121013 --width 32 --height 32 "RUST_BACKTRACE=1 ./target/debug/my_bot" "RUST_BACKTRACE=1 ./target/debug/v18"
+# Same games on the local engine (src/bin/match_runner.rs), no halite binary needed. Its maps are
+# not the official ones for these seeds:
+# ./target/debug/match_runner --seed 1548006861 --width 32 --height 32 --output-dir games/1548006861-32 "RUST_BACKTRACE=1 ./target/debug/my_bot" "RUST_BACKTRACE=1 ./target/debug/v10"
+# ./target/debug/match_runner --seed 1548024852 --width 32 --height 32 --output-dir games/1548024852-32 "RUST_BACKTRACE=1 ./target/debug/my_bot" "RUST_BACKTRACE=1 ./target/debug/v13"
+# ./target/debug/match_runner --seed 1548006861 --width 64 --height 64 --output-dir games/1548006861-64 "RUST_BACKTRACE=1 ./target/debug/my_bot" "RUST_BACKTRACE=1 ./target/debug/v15"
+# ./target/debug/match_runner --seed 1548121013 --width 64 --height 64 --output-dir games/1548121013-64 "RUST_BACKTRACE=1 ./target/debug/my_bot" "RUST_BACKTRACE=1 ./target/debug/v18"
+# ./target/debug/match_runner --seed 1548121013 --width 32 --height 32 --output-dir games/1548121013-32 "RUST_BACKTRACE=1 ./target/debug/my_bot" "RUST_BACKTRACE=1 ./target/debug/v18"
 
 # ./halite --replay-directory replays/ -vvv  --width 32 --height 32 "RUST_BACKTRACE=1 ./target/debug/my_bot" "RUST_BACKTRACE=1 ./target/debug/v18"
 # ./halite --replay-directory replays/ -vvv  --width 32 --height 32 "RUST_BACKTRACE=1 ./target/debug/my_bot" "RUST_BACKTRACE=1 ./target/debug/v18"
@@ -52,3 +59,8 @@ cargo build
 ./halite --replay-directory replays/ -vvv --width 64 --height 64 "RUST_BACKTRACE=1 ./target/debug/my_bot" "RUST_BACKTRACE=1 ./target/debug/v6" "RUST_BACKTRACE=1 ./target/debug/v6" "RUST_BACKTRACE=1 ./target/debug/v18"
 ./halite --replay-directory replays/ -vvv --width 64 --height 64 "RUST_BACKTRACE=1 ./target/debug/my_bot" "RUST_BACKTRACE=1 ./target/debug/v6" "RUST_BACKTRACE=1 ./target/debug/v6" "RUST_BACKTRACE=1 ./target/debug/v18"
 
+# Our bot seeing other rules, see src/hlt/rule_presets.rs for the presets:
+# ./halite --replay-directory replays/ -vvv --width 32 --height 32 "./target/debug/rules_shim --preset no-inspiration ./target/debug/my_bot" "./target/debug/v18"
+
+# Rated games between kept builds, ratings accumulate in games/ratings.json:
+# ./target/debug/tournament --games 40 --bot my_bot ./target/debug/my_bot --bot v18 ./target/debug/v18 --bot v15 ./target/debug/v15 --bot v13 ./target/debug/v13
@@ -0,0 +1,127 @@
+extern crate my_bot;
+
+use my_bot::hlt;
+use hlt::arena::Arena;
+use hlt::baseline;
+use hlt::bot::Bot;
+use hlt::constants::Constants;
+use hlt::json::Json;
+use hlt::log::Log;
+use hlt::rule_presets;
+use hlt::snapshot::Snapshot;
+use hlt::strategy::Strategy;
+use hlt::PlayerId;
+use std::env;
+use std::process::exit;
+
+const USAGE: &str = "usage: arena [--games N] [--seed N] [--size N] [--players 2|4] [--max-turns N]
+             [--opponent random|greedy|rammer|camper] [--preset NAME] [--constants FILE]
+             [--snapshot FILE]";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut games: usize = 100;
+    let mut seed: u64 = 1;
+    let mut size: usize = 32;
+    let mut num_players: usize = 2;
+    let mut max_turns: Option<usize> = None;
+    let mut opponent: Option<String> = None;
+    let mut constant_overrides: Vec<Json> = Vec::new();
+    let mut snapshot: Option<String> = None;
+
+    for pair in args.chunks(2) {
+        let value = match pair.get(1) {
+            Some(value) => value,
+            None => fail(&format!("{} needs a value", pair[0])),
+        };
+        match pair[0].as_str() {
+            "--games" => games = parse(value),
+            "--seed" => seed = parse(value),
+            "--size" => size = parse(value),
+            "--players" => num_players = parse(value),
+            "--max-turns" => max_turns = Some(parse(value)),
+            "--opponent" => opponent = Some(value.clone()),
+            "--preset" => match rule_presets::preset(value) {
+                Some(overrides) => constant_overrides.push(overrides),
+                None => fail(&format!("unknown preset {}", value)),
+            },
+            "--constants" => constant_overrides.push(rule_presets::load(value).unwrap_or_else(|e| fail(&e))),
+            "--snapshot" => snapshot = Some(value.clone()),
+            other => fail(&format!("unknown option {}", other)),
+        }
+    }
+    if num_players != 2 && num_players != 4 {
+        fail("games need 2 or 4 players");
+    }
+    if let Some(name) = &opponent {
+        if !baseline::NAMES.contains(&name.as_str()) {
+            fail(&format!("unknown opponent {}", name));
+        }
+    }
+
+    Log::mute();
+
+    let mut wins: Vec<usize> = vec![0; num_players];
+    let mut totals: Vec<usize> = vec![0; num_players];
+    for game_index in 0..games {
+        let game_seed = seed + game_index as u64;
+        // Every game starts from the snapshot when one is given, the seed then only
+        // changes what the baselines do.
+        let mut arena = match &snapshot {
+            Some(path) => Arena::from_snapshot(Snapshot::load(path).unwrap_or_else(|e| fail(&e))),
+            None => Arena::generate(game_seed, size, size, num_players).unwrap_or_else(|e| fail(&e)),
+        };
+        if arena.game.players.len() != num_players {
+            fail(&format!("the snapshot has {} players, use --players", arena.game.players.len()));
+        }
+        match rule_presets::apply(&arena.game.constants.to_json(), &constant_overrides) {
+            Ok(constants) => {
+                arena.game.constants = Constants::from_json(&constants).unwrap_or_else(|e| fail(&e.to_string()))
+            }
+            Err(message) => fail(&message),
+        }
+        if let Some(max_turns) = max_turns {
+            arena.game.constants.max_turns = max_turns;
+        }
+
+        // Seat 0 is always our bot, the other seats are our bot too unless an opponent is given.
+        let mut strategies: Vec<Box<dyn Strategy>> = (0..num_players)
+            .map(|i| {
+                let view = arena.view(PlayerId(i));
+                match &opponent {
+                    Some(name) if i > 0 => baseline::by_name(name, &view, game_seed + i as u64).unwrap(),
+                    _ => Box::new(Bot::new(&view, game_seed + i as u64)) as Box<dyn Strategy>,
+                }
+            })
+            .collect();
+        let scores = arena.play(&mut strategies);
+
+        let winner = (0..num_players).max_by_key(|i| (scores[*i], num_players - i)).unwrap();
+        wins[winner] += 1;
+        for (total, score) in totals.iter_mut().zip(&scores) {
+            *total += score;
+        }
+        println!("game {} seed {}: {:?}", game_index, game_seed, scores);
+    }
+
+    for seat in 0..num_players {
+        println!(
+            "seat {}: {} wins, average halite {}",
+            seat,
+            wins[seat],
+            totals[seat] / games.max(1)
+        );
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> T {
+    match value.parse() {
+        Ok(x) => x,
+        Err(_) => fail(&format!("can't parse '{}'", value)),
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    exit(1);
+}
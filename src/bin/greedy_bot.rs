@@ -0,0 +1,12 @@
+extern crate my_bot;
+
+use my_bot::hlt;
+use hlt::baseline::GreedyMiner;
+use hlt::game::Game;
+use hlt::strategy;
+
+fn main() {
+    let game = Game::new().unwrap_or_else(|e| e.exit());
+    let mut bot = GreedyMiner::new(&game);
+    strategy::run(game, &mut bot).exit();
+}
@@ -0,0 +1,115 @@
+extern crate my_bot;
+
+use my_bot::hlt;
+use hlt::json::Json;
+use hlt::rule_presets;
+use hlt::runner;
+use hlt::runner::MatchConfig;
+use std::env;
+use std::path::PathBuf;
+use std::process::exit;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+const USAGE: &str = "usage: match_runner [--width N] [--height N] [--seed N] [--output-dir DIR]
+                    [--turn-timeout MS] [--init-timeout MS] [--no-timeout] [--no-replay]
+                    [--preset NAME] [--constants FILE] [--set KEY=VALUE] [--snapshot FILE]
+                    [--save-snapshot TURN]
+                    \"<bot command>\" \"<bot command>\" [\"<bot command>\" \"<bot command>\"]";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let mut width: Option<usize> = None;
+    let mut height: Option<usize> = None;
+    let mut seed: Option<u64> = None;
+    let mut output_dir: Option<PathBuf> = None;
+    let mut init_timeout = Some(Duration::from_millis(30_000));
+    let mut turn_timeout = Some(Duration::from_millis(2_000));
+    let mut write_replay = true;
+    let mut constant_overrides: Vec<Json> = Vec::new();
+    let mut snapshot: Option<String> = None;
+    let mut save_snapshot_at: Option<usize> = None;
+    let mut bot_commands: Vec<String> = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        match arg {
+            "--width" | "--height" | "--seed" | "--output-dir" | "--turn-timeout" | "--init-timeout" | "--preset"
+            | "--constants" | "--set" | "--snapshot" | "--save-snapshot" => {
+                let value = match args.get(i + 1) {
+                    Some(value) => value,
+                    None => fail(&format!("{} needs a value", arg)),
+                };
+                match arg {
+                    "--width" => width = Some(parse(arg, value)),
+                    "--height" => height = Some(parse(arg, value)),
+                    "--seed" => seed = Some(parse(arg, value)),
+                    "--output-dir" => output_dir = Some(PathBuf::from(value)),
+                    "--turn-timeout" => turn_timeout = Some(Duration::from_millis(parse(arg, value))),
+                    "--preset" => match rule_presets::preset(value) {
+                        Some(overrides) => constant_overrides.push(overrides),
+                        None => fail(&format!("unknown preset {}", value)),
+                    },
+                    "--constants" => constant_overrides.push(rule_presets::load(value).unwrap_or_else(|e| fail(&e))),
+                    "--snapshot" => snapshot = Some(value.clone()),
+                    "--save-snapshot" => save_snapshot_at = Some(parse(arg, value)),
+                    "--set" => constant_overrides.push(rule_presets::parse_assignment(value).unwrap_or_else(|e| fail(&e))),
+                    _ => init_timeout = Some(Duration::from_millis(parse(arg, value))),
+                }
+                i += 2;
+                continue;
+            }
+            "--no-timeout" => {
+                init_timeout = None;
+                turn_timeout = None;
+            }
+            "--no-replay" => write_replay = false,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with("--") => fail(&format!("unknown option {}", arg)),
+            _ => bot_commands.push(arg.to_string()),
+        }
+        i += 1;
+    }
+
+    let seed = seed.unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs());
+    let size = runner::MAP_SIZES[(seed % runner::MAP_SIZES.len() as u64) as usize];
+    let width = width.or(height).unwrap_or(size);
+    let height = height.unwrap_or(width);
+
+    let config = MatchConfig {
+        bot_commands,
+        width,
+        height,
+        seed,
+        init_timeout,
+        turn_timeout,
+        output_dir: output_dir.unwrap_or_else(|| PathBuf::from(format!("games/{}", seed))),
+        write_replay,
+        constant_overrides,
+        snapshot,
+        save_snapshot_at,
+    };
+
+    match runner::run_match(&config) {
+        Ok(result) => println!("{}", result.to_json()),
+        Err(message) => fail(&message),
+    }
+}
+
+fn parse<T: std::str::FromStr>(arg: &str, value: &str) -> T {
+    match value.parse() {
+        Ok(x) => x,
+        Err(_) => fail(&format!("can't parse '{}' for {}", value, arg)),
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    exit(1);
+}
@@ -0,0 +1,15 @@
+extern crate my_bot;
+
+use my_bot::hlt;
+use hlt::baseline::RandomMover;
+use hlt::game::Game;
+use hlt::strategy;
+use std::env;
+
+fn main() {
+    // The seed is the only argument, so runs can be repeated exactly.
+    let seed: u64 = env::args().nth(1).and_then(|x| x.parse().ok()).unwrap_or(0);
+    let game = Game::new().unwrap_or_else(|e| e.exit());
+    let mut bot = RandomMover::new(&game, seed);
+    strategy::run(game, &mut bot).exit();
+}
@@ -0,0 +1,94 @@
+extern crate my_bot;
+
+use my_bot::hlt;
+use hlt::json::Json;
+use hlt::rule_presets;
+use std::env;
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+use std::process::exit;
+use std::process::Command as Process;
+use std::process::Stdio;
+
+const USAGE: &str = "usage: rules_shim [--preset NAME] [--constants FILE] [--set KEY=VALUE] ... \"<bot command>\"
+
+Runs the bot with the first line from the engine, the constants block, rewritten.
+Options apply in order, later ones win.";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut overrides: Vec<Json> = Vec::new();
+    let mut bot_command: Option<String> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        let value = match arg {
+            "--preset" | "--constants" | "--set" => match args.get(i + 1) {
+                Some(value) => value.as_str(),
+                None => fail(&format!("{} needs a value", arg)),
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if arg.starts_with("--") => fail(&format!("unknown option {}", arg)),
+            _ if bot_command.is_none() => {
+                bot_command = Some(arg.to_string());
+                i += 1;
+                continue;
+            }
+            _ => fail("give the bot command as one argument"),
+        };
+
+        let override_set = match arg {
+            "--preset" => rule_presets::preset(value).ok_or_else(|| format!("unknown preset {}", value)),
+            "--constants" => rule_presets::load(value),
+            _ => rule_presets::parse_assignment(value),
+        };
+        match override_set {
+            Ok(override_set) => overrides.push(override_set),
+            Err(message) => fail(&message),
+        }
+        i += 2;
+    }
+
+    let bot_command = match bot_command {
+        Some(command) => command,
+        None => fail("missing bot command"),
+    };
+
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut constants_line = String::new();
+    if input.read_line(&mut constants_line).is_err() || constants_line.trim().is_empty() {
+        fail("no constants line from the engine");
+    }
+
+    let constants = match Json::parse(constants_line.trim()).and_then(|x| rule_presets::apply(&x, &overrides)) {
+        Ok(constants) => constants,
+        Err(message) => fail(&message),
+    };
+
+    // The bot talks to the engine directly on stdout and stderr, only its input passes through here.
+    let mut child = match Process::new("sh").arg("-c").arg(&bot_command).stdin(Stdio::piped()).spawn() {
+        Ok(child) => child,
+        Err(e) => fail(&format!("can't start '{}': {}", bot_command, e)),
+    };
+
+    {
+        let mut bot_input = child.stdin.take().unwrap();
+        if writeln!(bot_input, "{}", constants).is_ok() {
+            let _ = io::copy(&mut input, &mut bot_input);
+        }
+    }
+
+    let status = child.wait().map(|x| x.code().unwrap_or(1)).unwrap_or(1);
+    exit(status);
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    exit(1);
+}
@@ -0,0 +1,56 @@
+extern crate my_bot;
+
+use my_bot::hlt;
+use hlt::log::Log;
+use hlt::replay_reader::Replay;
+use hlt::snapshot::Snapshot;
+use hlt::transcript::Transcript;
+use std::env;
+use std::process::exit;
+
+const USAGE: &str = "usage: snapshot [--transcript] <replay.json> <turn> <snapshot.json>
+
+Saves the state at the start of <turn> of the replay, to be played on with
+match_runner --snapshot or arena --snapshot. With --transcript the game is read from
+a bot-N.transcript written by a bot run with --record, for games that have no replay.";
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|x| x == "-h" || x == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let from_transcript = args.iter().any(|x| x == "--transcript");
+    args.retain(|x| x != "--transcript");
+    if args.len() != 3 {
+        fail("expected a replay, a turn and an output file");
+    }
+    let turn: usize = args[1].parse().unwrap_or_else(|_| fail(&format!("can't parse turn '{}'", args[1])));
+
+    Log::mute();
+    let snapshot = if from_transcript {
+        let transcript = Transcript::load(&args[0]).unwrap_or_else(|e| fail(&e));
+        Snapshot::from_transcript(&transcript, turn)
+    } else {
+        let replay = Replay::load(&args[0]).unwrap_or_else(|e| fail(&e));
+        Snapshot::from_replay(&replay, turn)
+    };
+    let snapshot = snapshot.unwrap_or_else(|e| fail(&e));
+    snapshot.save(&args[2]).unwrap_or_else(|e| fail(&e));
+
+    let game = &snapshot.game;
+    let banks: Vec<usize> = game.players.iter().map(|player| player.halite).collect();
+    println!(
+        "turn {} of {}: {} ships, {} dropoffs, banks {:?}",
+        game.turn_number,
+        game.constants.max_turns,
+        game.ships.len(),
+        game.dropoffs.len(),
+        banks
+    );
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    exit(1);
+}
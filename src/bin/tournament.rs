@@ -0,0 +1,129 @@
+extern crate my_bot;
+
+use my_bot::hlt;
+use hlt::runner::MatchResult;
+use hlt::tournament;
+use hlt::tournament::Contestant;
+use hlt::tournament::TournamentConfig;
+use std::env;
+use std::path::PathBuf;
+use std::process::exit;
+use std::thread;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+const USAGE: &str = "usage: tournament [--games N] [--seed N] [--threads N] [--ratings FILE] [--output-dir DIR]
+                  [--turn-timeout MS] [--no-timeout] [--no-replay]
+                  --bot NAME \"<bot command>\" --bot NAME \"<bot command>\" ...";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let mut games: usize = 20;
+    let mut seed: Option<u64> = None;
+    // Every game runs two or four bot processes, so leave some cores for them.
+    let mut threads = thread::available_parallelism().map(|x| (x.get() / 2).max(1)).unwrap_or(1);
+    let mut ratings_path = PathBuf::from("games/ratings.json");
+    let mut output_dir = PathBuf::from("games/tournament");
+    let mut init_timeout = Some(Duration::from_millis(30_000));
+    let mut turn_timeout = Some(Duration::from_millis(2_000));
+    let mut write_replays = true;
+    let mut contestants: Vec<Contestant> = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        let value = |offset: usize| match args.get(i + offset) {
+            Some(value) => value.clone(),
+            None => fail(&format!("{} needs a value", arg)),
+        };
+        match arg {
+            "--games" => games = parse(arg, &value(1)),
+            "--seed" => seed = Some(parse(arg, &value(1))),
+            "--threads" => threads = parse(arg, &value(1)),
+            "--ratings" => ratings_path = PathBuf::from(value(1)),
+            "--output-dir" => output_dir = PathBuf::from(value(1)),
+            "--turn-timeout" => turn_timeout = Some(Duration::from_millis(parse(arg, &value(1)))),
+            "--no-timeout" => {
+                init_timeout = None;
+                turn_timeout = None;
+                i += 1;
+                continue;
+            }
+            "--no-replay" => {
+                write_replays = false;
+                i += 1;
+                continue;
+            }
+            "--bot" => {
+                contestants.push(Contestant { name: value(1), command: value(2) });
+                i += 3;
+                continue;
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => fail(&format!("unknown argument {}", arg)),
+        }
+        i += 2;
+    }
+
+    if contestants.len() < 2 {
+        fail("a tournament needs at least 2 bots");
+    }
+    if let Some(name) = contestants.iter().map(|x| &x.name).find(|name| contestants.iter().filter(|x| &x.name == *name).count() > 1) {
+        fail(&format!("bot name {} is used twice", name));
+    }
+
+    let config = TournamentConfig {
+        contestants,
+        games,
+        seed: seed.unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()),
+        threads,
+        init_timeout,
+        turn_timeout,
+        output_dir,
+        write_replays,
+        ratings_path,
+    };
+
+    let mut report = |spec: &tournament::GameSpec, result: &Result<MatchResult, String>| {
+        let names: Vec<&str> = spec.seats.iter().map(|x| config.contestants[*x].name.as_str()).collect();
+        match result {
+            Ok(result) => {
+                let scores: Vec<String> = result
+                    .players
+                    .iter()
+                    .zip(&names)
+                    .map(|(player, name)| format!("{} {}", name, player.halite))
+                    .collect();
+                println!("game {} seed {} {}x{}: {}", spec.index, spec.seed, spec.size, spec.size, scores.join(", "));
+            }
+            Err(message) => eprintln!("game {} seed {} failed: {}", spec.index, spec.seed, message),
+        }
+    };
+
+    let table = match tournament::run_tournament(&config, &mut report) {
+        Ok(table) => table,
+        Err(message) => fail(&message),
+    };
+
+    println!();
+    for (name, rating) in table.standings() {
+        println!("{:>20} {:>7.1} {:>5} games {:>5} wins", name, rating.rating, rating.games, rating.wins);
+    }
+}
+
+fn parse<T: std::str::FromStr>(arg: &str, value: &str) -> T {
+    match value.parse() {
+        Ok(x) => x,
+        Err(_) => fail(&format!("can't parse '{}' for {}", value, arg)),
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    exit(1);
+}
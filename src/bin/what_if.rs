@@ -0,0 +1,73 @@
+extern crate my_bot;
+
+use my_bot::hlt;
+use hlt::log::Log;
+use hlt::replay_reader::Replay;
+use hlt::what_if;
+use hlt::PlayerId;
+use std::env;
+use std::process::exit;
+
+const USAGE: &str = "usage: what_if <replay.json> <turn> [--player N] [--fallback random|greedy|rammer|camper]
+
+Plays the replay from the start of <turn> with the current bot as player N (default 0).
+The other players repeat their recorded commands until their game differs from the
+replay, then the fallback baseline (default greedy) plays for them.";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut positional: Vec<&str> = Vec::new();
+    let mut player: usize = 0;
+    let mut fallback = "greedy".to_string();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--player" | "--fallback" => {
+                let value = match args.get(i + 1) {
+                    Some(value) => value,
+                    None => fail(&format!("{} needs a value", args[i])),
+                };
+                if args[i] == "--player" {
+                    player = value.parse().unwrap_or_else(|_| fail(&format!("can't parse player '{}'", value)));
+                } else {
+                    fallback = value.clone();
+                }
+                i += 2;
+                continue;
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            arg => positional.push(arg),
+        }
+        i += 1;
+    }
+    if positional.len() != 2 {
+        fail("expected a replay and a turn");
+    }
+    let turn: usize = positional[1].parse().unwrap_or_else(|_| fail(&format!("can't parse turn '{}'", positional[1])));
+
+    Log::mute();
+    let replay = Replay::load(positional[0]).unwrap_or_else(|e| fail(&e));
+    let result = what_if::run(&replay, turn, PlayerId(player), &fallback).unwrap_or_else(|e| fail(&e));
+
+    println!("resimulated from turn {} with the current bot as player {}", result.start_turn, player);
+    for (i, name) in replay.names.iter().enumerate() {
+        let role = match result.diverged_at[i] {
+            _ if i == player => "current bot".to_string(),
+            Some(turn) => format!("replayed until turn {}, then {}", turn, fallback),
+            None => "replayed to the end".to_string(),
+        };
+        println!(
+            "player {} {}: {} (recorded {}), {}",
+            i, name, result.final_halite[i], result.recorded_halite[i], role
+        );
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}\n{}", message, USAGE);
+    exit(1);
+}
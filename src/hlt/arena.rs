@@ -0,0 +1,74 @@
+use hlt::command::Command;
+use hlt::constants::Constants;
+use hlt::engine::Engine;
+use hlt::engine::TurnResult;
+use hlt::game::Game;
+use hlt::map_generator::MapGenerator;
+use hlt::runner::INITIAL_HALITE;
+use hlt::snapshot::Snapshot;
+use hlt::strategy::Strategy;
+use hlt::PlayerId;
+
+// Plays strategies against each other inside one process. Every seat is handed its own
+// view of the authoritative game each turn, so no text protocol is involved.
+pub struct Arena {
+    pub game: Game,
+    engine: Engine,
+}
+
+impl Arena {
+    pub fn new(mut game: Game) -> Arena {
+        if game.turn_number == 0 {
+            game.turn_number = 1;
+        }
+        let engine = Engine::new(&game);
+        Arena { game, engine }
+    }
+
+    pub fn generate(seed: u64, width: usize, height: usize, num_players: usize) -> Result<Arena, String> {
+        let constants = Constants::official(width.max(height));
+        let game = MapGenerator::new(seed).generate_game(width, height, num_players, constants, INITIAL_HALITE)?;
+        Ok(Arena::new(game))
+    }
+
+    pub fn from_snapshot(snapshot: Snapshot) -> Arena {
+        let mut arena = Arena::new(snapshot.game);
+        arena.engine = Engine::resume(snapshot.next_ship_id, snapshot.next_dropoff_id);
+        arena
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let (next_ship_id, next_dropoff_id) = self.engine.next_ids();
+        Snapshot { game: self.game.view_for(PlayerId(0)), next_ship_id, next_dropoff_id }
+    }
+
+    pub fn view(&self, player_id: PlayerId) -> Game {
+        self.game.view_for(player_id)
+    }
+
+    pub fn is_over(&self) -> bool {
+        Engine::is_over(&self.game)
+    }
+
+    pub fn step(&mut self, strategies: &mut [Box<dyn Strategy>]) -> TurnResult {
+        let commands: Vec<Vec<Command>> = strategies
+            .iter_mut()
+            .enumerate()
+            .map(|(i, strategy)| strategy.play_turn(&self.view(PlayerId(i))))
+            .collect();
+        self.resolve(&commands)
+    }
+
+    // For callers that gather the commands themselves.
+    pub fn resolve(&mut self, commands: &[Vec<Command>]) -> TurnResult {
+        self.engine.resolve_turn(&mut self.game, commands)
+    }
+
+    // Plays to the end and returns every player's final halite.
+    pub fn play(&mut self, strategies: &mut [Box<dyn Strategy>]) -> Vec<usize> {
+        while !self.is_over() {
+            self.step(strategies);
+        }
+        self.game.players.iter().map(|player| player.halite).collect()
+    }
+}
@@ -0,0 +1,280 @@
+use hlt::command::Command;
+use hlt::direction::Direction;
+use hlt::engine::Engine;
+use hlt::game::Game;
+use hlt::position::Position;
+use hlt::rng;
+use hlt::ship::Ship;
+use hlt::strategy::Strategy;
+use hlt::ShipId;
+use rand::prng::XorShiftRng;
+use rand::Rng;
+use std::collections::HashSet;
+
+// Simple reference opponents. None of them look further than a few cells, and given
+// the same seed and the same frames they always send the same commands.
+
+pub const NAMES: [&str; 4] = ["random", "greedy", "rammer", "camper"];
+
+pub fn by_name(name: &str, game: &Game, seed: u64) -> Option<Box<dyn Strategy>> {
+    match name {
+        "random" => Some(Box::new(RandomMover::new(game, seed))),
+        "greedy" => Some(Box::new(GreedyMiner::new(game))),
+        "rammer" => Some(Box::new(Rammer::new(game))),
+        "camper" => Some(Box::new(ShipyardCamper::new(game))),
+        _ => None,
+    }
+}
+
+// Collects one turn of commands while keeping our own ships from running into each
+// other: a ship may only move to a cell no ship of ours has claimed yet and that isn't
+// the current cell of a ship still waiting for its order.
+struct Orders<'a> {
+    game: &'a Game,
+    claimed: HashSet<Position>,
+    waiting: HashSet<Position>,
+    commands: Vec<Command>,
+}
+
+impl<'a> Orders<'a> {
+    fn new(game: &'a Game) -> Orders<'a> {
+        let waiting = game.players[game.my_id.0]
+            .ship_ids
+            .iter()
+            .map(|id| game.ships[id].position)
+            .collect();
+        Orders { game, claimed: HashSet::new(), waiting, commands: Vec::new() }
+    }
+
+    fn my_ships(game: &Game) -> Vec<&Ship> {
+        game.players[game.my_id.0].ship_ids.iter().map(|id| &game.ships[id]).collect()
+    }
+
+    fn can_move(&self, ship: &Ship) -> bool {
+        let cell_halite = self.game.map.at_position(&ship.position).halite;
+        ship.halite >= Engine::move_cost(cell_halite, self.game.constants.move_cost_ratio)
+    }
+
+    // `crash_at_home` lets the ship share one of our structures, which only makes sense
+    // on the last turns when everybody is dumping cargo.
+    fn order(&mut self, ship: &Ship, direction: Direction, crash_at_home: bool) {
+        self.waiting.remove(&ship.position);
+
+        let target = self.game.map.normalize(&ship.position.directional_offset(direction));
+        let at_home = crash_at_home && Engine::structure_owner(self.game, &target) == Some(self.game.my_id);
+        let free = !self.claimed.contains(&target) && !self.waiting.contains(&target);
+        if direction != Direction::Still && self.can_move(ship) && (free || at_home) {
+            self.claimed.insert(target);
+            self.commands.push(ship.move_ship(direction));
+        } else {
+            self.claimed.insert(ship.position);
+            self.commands.push(ship.stay_still());
+        }
+    }
+
+    fn spawn_if(&mut self, wanted: bool) {
+        let game = self.game;
+        let shipyard = game.players[game.my_id.0].shipyard.position;
+        let affordable = game.players[game.my_id.0].halite >= game.constants.ship_cost;
+        if wanted && affordable && !self.claimed.contains(&shipyard) {
+            self.commands.push(Command::spawn_ship());
+        }
+    }
+
+    fn finish(self) -> Vec<Command> {
+        self.commands
+    }
+}
+
+// First cardinal direction, in get_all_cardinals order, that gets closer to the target.
+fn toward(game: &Game, from: &Position, to: &Position) -> Direction {
+    let distance = game.map.calculate_distance(from, to);
+    Direction::get_all_cardinals()
+        .into_iter()
+        .find(|d| game.map.calculate_distance(&from.directional_offset(*d), to) < distance)
+        .unwrap_or(Direction::Still)
+}
+
+fn nearest_home(game: &Game, from: &Position) -> Position {
+    let player = &game.players[game.my_id.0];
+    let mut homes: Vec<Position> = vec![player.shipyard.position];
+    homes.extend(player.dropoff_ids.iter().map(|id| game.dropoffs[id].position));
+    homes.into_iter().min_by_key(|home| game.map.calculate_distance(from, home)).unwrap()
+}
+
+fn turns_left(game: &Game) -> usize {
+    game.constants.max_turns.saturating_sub(game.turn_number)
+}
+
+fn early_game(game: &Game) -> bool {
+    game.turn_number <= game.constants.max_turns / 2
+}
+
+// Every ship picks a random direction (or stays) each turn; spawns on a coin flip
+// during the first half of the game.
+pub struct RandomMover {
+    rng: XorShiftRng,
+}
+
+impl RandomMover {
+    pub fn new(_game: &Game, seed: u64) -> RandomMover {
+        RandomMover { rng: rng::seeded(seed) }
+    }
+}
+
+impl Strategy for RandomMover {
+    fn name(&self) -> String {
+        "baseline random".to_string()
+    }
+
+    fn play_turn(&mut self, game: &Game) -> Vec<Command> {
+        let mut orders = Orders::new(game);
+        let directions = [Direction::North, Direction::South, Direction::East, Direction::West, Direction::Still];
+        for ship in Orders::my_ships(game) {
+            let direction = directions[self.rng.gen_range(0, directions.len())];
+            orders.order(ship, direction, false);
+        }
+        let coin: bool = self.rng.gen();
+        orders.spawn_if(coin && early_game(game));
+        orders.finish()
+    }
+}
+
+// Mines wherever it stands while the cell is rich, otherwise walks to the best cell
+// nearby; heads home when nearly full and for the final dump.
+pub struct GreedyMiner {
+    radius: i32,
+}
+
+impl GreedyMiner {
+    pub fn new(_game: &Game) -> GreedyMiner {
+        GreedyMiner { radius: 5 }
+    }
+
+    fn best_cell(&self, game: &Game, from: &Position) -> Position {
+        let mut best = *from;
+        let mut best_score = game.map.at_position(from).halite as f64;
+        for dy in -self.radius..=self.radius {
+            for dx in -self.radius..=self.radius {
+                let position = game.map.normalize(&Position { x: from.x + dx, y: from.y + dy });
+                let cell = game.map.at_position(&position);
+                if cell.structure.is_some() {
+                    continue;
+                }
+                let score = cell.halite as f64 / (game.map.calculate_distance(from, &position) + 1) as f64;
+                if score > best_score {
+                    best = position;
+                    best_score = score;
+                }
+            }
+        }
+        best
+    }
+}
+
+impl Strategy for GreedyMiner {
+    fn name(&self) -> String {
+        "baseline greedy".to_string()
+    }
+
+    fn play_turn(&mut self, game: &Game) -> Vec<Command> {
+        let mut orders = Orders::new(game);
+        let rich = game.constants.max_halite / 10;
+        for ship in Orders::my_ships(game) {
+            let home = nearest_home(game, &ship.position);
+            let distance_home = game.map.calculate_distance(&ship.position, &home);
+            let final_dump = turns_left(game) <= distance_home + 5;
+
+            let direction = if final_dump || ship.halite >= game.constants.max_halite * 9 / 10 {
+                toward(game, &ship.position, &home)
+            } else if game.map.at_position(&ship.position).halite >= rich {
+                Direction::Still
+            } else {
+                toward(game, &ship.position, &self.best_cell(game, &ship.position))
+            };
+            orders.order(ship, direction, final_dump);
+        }
+        orders.spawn_if(early_game(game));
+        orders.finish()
+    }
+}
+
+// Chases enemy ships, preferring loaded ones close by, and never mines on purpose.
+pub struct Rammer {}
+
+impl Rammer {
+    pub fn new(_game: &Game) -> Rammer {
+        Rammer {}
+    }
+}
+
+impl Strategy for Rammer {
+    fn name(&self) -> String {
+        "baseline rammer".to_string()
+    }
+
+    fn play_turn(&mut self, game: &Game) -> Vec<Command> {
+        let mut orders = Orders::new(game);
+        let mut targets: Vec<&Ship> = game.ships.values().filter(|ship| ship.owner != game.my_id).collect();
+        targets.sort_by_key(|ship| ship.id.0);
+
+        for ship in Orders::my_ships(game) {
+            let target = targets.iter().max_by(|a, b| {
+                let score = |x: &Ship| (x.halite + 1) as f64 / (game.map.calculate_distance(&ship.position, &x.position) + 1) as f64;
+                score(a).partial_cmp(&score(b)).unwrap().then(b.id.0.cmp(&a.id.0))
+            });
+            let direction = match target {
+                Some(target) => toward(game, &ship.position, &target.position),
+                None => Direction::Still,
+            };
+            orders.order(ship, direction, false);
+        }
+        orders.spawn_if(early_game(game));
+        orders.finish()
+    }
+}
+
+// Parks ships on the enemy shipyards and, once those are taken, on the cells around
+// them, so enemy spawns and returning ships collide with it.
+pub struct ShipyardCamper {
+    ships_per_enemy: usize,
+}
+
+impl ShipyardCamper {
+    pub fn new(_game: &Game) -> ShipyardCamper {
+        ShipyardCamper { ships_per_enemy: 3 }
+    }
+
+    fn post(&self, game: &Game, ship_index: usize) -> Position {
+        let enemies = game.enemy_players();
+        let shipyard = enemies[ship_index % enemies.len()].shipyard.position;
+        match ship_index / enemies.len() {
+            0 => shipyard,
+            rank => {
+                let direction = Direction::get_all_cardinals()[(rank - 1) % 4];
+                game.map.normalize(&shipyard.directional_offset(direction))
+            }
+        }
+    }
+}
+
+impl Strategy for ShipyardCamper {
+    fn name(&self) -> String {
+        "baseline camper".to_string()
+    }
+
+    fn play_turn(&mut self, game: &Game) -> Vec<Command> {
+        let mut orders = Orders::new(game);
+        let mut ship_ids: Vec<ShipId> = game.players[game.my_id.0].ship_ids.clone();
+        ship_ids.sort_by_key(|id| id.0);
+
+        for (i, ship_id) in ship_ids.iter().enumerate() {
+            let ship = &game.ships[ship_id];
+            let post = self.post(game, i);
+            orders.order(ship, toward(game, &ship.position, &post), false);
+        }
+        let wanted = ship_ids.len() < self.ships_per_enemy * game.enemy_players().len();
+        orders.spawn_if(wanted && early_game(game));
+        orders.finish()
+    }
+}
@@ -0,0 +1,224 @@
+use hlt::command::Command;
+use hlt::game_event::GameEvent;
+use hlt::game::Game;
+use hlt::gradient_map::GradientMap;
+use hlt::log::Log;
+use hlt::navi::Navi;
+use hlt::rng;
+use hlt::scoreboard::Scoreboard;
+use hlt::ship_registry::ShipRegistry;
+use hlt::strategy::Strategy;
+use hlt::turn_inference::TurnInference;
+use hlt::ShipId;
+use rand::prng::XorShiftRng;
+
+//ignore number am bad at remembering to update version
+pub const NAME: &str = "mellow root v20";
+
+pub struct Bot {
+    pub navi: Navi,
+    // What happened since the frame we last played, derived at the start of every turn.
+    pub events: Vec<GameEvent>,
+    // What every player most likely did on that turn, worked out from `events`.
+    pub inference: Option<TurnInference>,
+    previous: Option<Game>,
+    pub registry: ShipRegistry,
+    pub scoreboard: Scoreboard,
+    // All of the bot's randomness comes from here, so a seed and the engine's frames
+    // pin down every command.
+    pub rng: XorShiftRng,
+}
+
+impl Bot {
+    pub fn new(game: &Game, seed: u64) -> Bot {
+        Bot {
+            navi: Navi::new(game.map.width, game.map.height, game),
+            events: Vec::new(),
+            inference: None,
+            previous: None,
+            registry: ShipRegistry::new(),
+            scoreboard: Scoreboard::new(game),
+            rng: rng::seeded(seed),
+        }
+    }
+}
+
+impl Strategy for Bot {
+    fn name(&self) -> String {
+        NAME.to_string()
+    }
+
+    fn play_turn(&mut self, game: &Game) -> Vec<Command> {
+        self.events = match &self.previous {
+            Some(previous) => GameEvent::derive(previous, game),
+            None => Vec::new(),
+        };
+        self.inference = match &self.previous {
+            Some(previous) => match TurnInference::from_events(previous, game, &self.events) {
+                Ok(inference) => Some(inference),
+                Err(e) => {
+                    Log::log(&format!("turn inference: {}", e));
+                    None
+                }
+            },
+            None => None,
+        };
+        self.scoreboard.update(self.previous.as_ref(), game);
+        self.previous = Some(game.view_for(game.my_id));
+        for ship_id in self.registry.update(game, &self.events) {
+            self.navi.forget_ship(ship_id);
+        }
+
+        let mut gradient_map = GradientMap::construct(game, &mut self.rng);
+        gradient_map.initialize(game, &self.navi, &mut self.rng);
+        self.navi.update_frame(game, &gradient_map);
+
+        let me = &game.players[game.my_id.0];
+
+        let mut command_queue: Vec<Command> = Vec::new();
+        let mut command_order: Vec<ShipId> = Vec::new();
+
+        command_order.append(&mut self.navi.are_stalled);
+        command_order.append(&mut self.navi.at_dropoff);
+        for (d, ship_ids) in &mut self.navi.coming_home {
+            if *d == 1 {
+                let new_vec = ship_ids.clone();
+                let mut ship_ids = Navi::sort_adjacent_dropoff(new_vec, &gradient_map, game);
+                command_order.append(&mut ship_ids);
+            } else {
+                command_order.append(ship_ids);
+            }
+        }
+
+        let mut i = game.map.width;
+        while i > 0 {
+            if self.navi.gathering.contains_key(&i) {
+                let new_vec = self.navi.gathering.get_mut(&i).unwrap();
+                command_order.append(new_vec);
+            }
+            i -= 1;
+        }
+
+        self.navi.clear();
+
+        for ship_id in &command_order {
+            // once you fix colissions remove this
+            if game.ships.contains_key(ship_id) {
+                let ship = &game.ships[ship_id];
+                let command = self.navi.suggest_move(&mut gradient_map, ship, game, &mut self.rng);
+                self.navi.process_move(*ship_id);
+                command_queue.push(command);
+            }
+        }
+
+        for ship_id in &me.ship_ids {
+            if !self.navi.have_moved.contains_key(ship_id) {
+                self.navi.update_for_new_ship(*ship_id);
+
+                let ship = &game.ships[ship_id];
+                let command = self.navi.suggest_move(&mut gradient_map, ship, game, &mut self.rng);
+                self.navi.process_move(*ship_id);
+                command_queue.push(command);
+            }
+        }
+
+        let mut saving_for_d_off = 0;
+        if self.navi.this_turn_dropoff {
+            saving_for_d_off = game.constants.dropoff_cost;
+        }
+
+        let mut production = 2000;
+        if !command_queue.is_empty() {
+            production = game.map.initial_halite / game.players.len() / command_queue.len();
+        }
+
+        if game.players.len() == 2 {
+            if me.halite >= game.constants.ship_cost + saving_for_d_off
+                && !gradient_map.at_position(&me.shipyard.position).my_occupy
+                && (self.scoreboard.enemy_ships_alive(game.my_id) + 1 > self.scoreboard.ships_alive(game.my_id)
+                    && game.constants.max_turns.saturating_sub(game.turn_number) > 100)
+            {
+                command_queue.push(me.shipyard.spawn());
+            }
+        } else {
+            if (production > 1500
+                || Game::half_halite_collected(
+                    &game.map.initial_halite,
+                    &gradient_map.halite_remaining,
+                ))
+                && me.halite >= game.constants.ship_cost + saving_for_d_off
+                && !gradient_map.at_position(&me.shipyard.position).my_occupy
+                && game.constants.max_turns.saturating_sub(game.turn_number) > 200
+            {
+                command_queue.push(me.shipyard.spawn());
+            }
+        }
+
+        self.navi.end_turn();
+        command_order.clear();
+
+        command_queue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::arena::Arena;
+    use hlt::bot::Bot;
+    use hlt::command::Command;
+    use hlt::constants::Constants;
+    use hlt::rule_presets;
+    use hlt::strategy::Strategy;
+    use hlt::PlayerId;
+
+    // The rules shim shortens the game for the bot only; the server keeps sending frames
+    // after the preset's MAX_TURNS.
+    #[test]
+    fn short_game_recalls_ships_and_plays_past_its_last_turn() {
+        let mut arena = Arena::generate(3, 32, 32, 2).unwrap();
+        let short_game = rule_presets::preset("short-game").unwrap();
+        let constants = rule_presets::apply(&arena.game.constants.to_json(), &[short_game]).unwrap();
+        arena.game.constants = Constants::from_json(&constants).unwrap();
+        let max_turns = arena.game.constants.max_turns;
+        assert_eq!(max_turns, 200);
+
+        let mut bots: Vec<Bot> = (0..2).map(|i| Bot::new(&arena.view(PlayerId(i)), i as u64)).collect();
+        let mut recalled = false;
+        while arena.game.turn_number <= max_turns + 20 {
+            let commands: Vec<Vec<Command>> = bots
+                .iter_mut()
+                .enumerate()
+                .map(|(i, bot)| bot.play_turn(&arena.view(PlayerId(i))))
+                .collect();
+
+            if arena.game.turn_number == max_turns - 5 {
+                let ship_ids = &arena.game.players[0].ship_ids;
+                assert!(!ship_ids.is_empty());
+                recalled = ship_ids.iter().all(|id| bots[0].navi.end_game.get(id) == Some(&true));
+            }
+            arena.resolve(&commands);
+        }
+        assert!(recalled, "ships were not in end game mode near MAX_TURNS");
+    }
+
+    // The two player spawn rule used to count ships straight from the frame; the
+    // scoreboard, updated at the start of play_turn, has to give the same counts.
+    #[test]
+    fn scoreboard_counts_match_the_frame() {
+        let mut arena = Arena::generate(5, 32, 32, 2).unwrap();
+        let mut bots: Vec<Bot> = (0..2).map(|i| Bot::new(&arena.view(PlayerId(i)), i as u64)).collect();
+        while arena.game.turn_number <= 100 {
+            let mut commands: Vec<Vec<Command>> = Vec::new();
+            for (i, bot) in bots.iter_mut().enumerate() {
+                let game = arena.view(PlayerId(i));
+                commands.push(bot.play_turn(&game));
+
+                let me = &game.players[i];
+                assert_eq!(bot.scoreboard.ships_alive(me.id), me.ship_ids.len());
+                assert_eq!(bot.scoreboard.enemy_ships_alive(me.id), game.ships.len() - me.ship_ids.len());
+            }
+            arena.resolve(&commands);
+        }
+        assert!(!arena.game.players[0].ship_ids.is_empty());
+    }
+}
@@ -1,18 +1,115 @@
 use hlt::direction::Direction;
 use hlt::ShipId;
+use std::fmt;
+use std::str::FromStr;
 
-pub struct Command(pub String);
+// One order for the server. Display gives the wire format ("g", "c 3", "m 3 n") and
+// parse reads it back.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub enum Command {
+    Spawn,
+    Move(ShipId, Direction),
+    Construct(ShipId),
+}
 
 impl Command {
     pub fn spawn_ship() -> Command {
-        Command(format!("g"))
+        Command::Spawn
     }
 
     pub fn transform_ship_into_dropoff_site(ship_id: ShipId) -> Command {
-        Command(format!("c {}", ship_id.0))
+        Command::Construct(ship_id)
     }
 
     pub fn move_ship(ship_id: ShipId, direction: Direction) -> Command {
-        Command(format!("m {} {}", ship_id.0, direction.get_char_encoding()))
+        Command::Move(ship_id, direction)
+    }
+
+    // The ship the command is for, None for spawns.
+    pub fn ship_id(&self) -> Option<ShipId> {
+        match *self {
+            Command::Spawn => None,
+            Command::Move(ship_id, _) | Command::Construct(ship_id) => Some(ship_id),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Command, String> {
+        let tokens: Vec<&str> = text.split_whitespace().collect();
+        let ship_id = |token: &str| {
+            token.parse().map(ShipId).map_err(|_| format!("bad ship id '{}' in command '{}'", token, text))
+        };
+        match tokens.as_slice() {
+            ["g"] => Ok(Command::Spawn),
+            ["c", id] => Ok(Command::Construct(ship_id(id)?)),
+            ["m", id, direction] => {
+                let mut chars = direction.chars();
+                let direction = match (chars.next(), chars.next()) {
+                    (Some(c), None) => Direction::from_char_encoding(c),
+                    _ => None,
+                };
+                match direction {
+                    Some(direction) => Ok(Command::Move(ship_id(id)?, direction)),
+                    None => Err(format!("bad direction in command '{}'", text)),
+                }
+            }
+            _ => Err(format!("unrecognised command '{}'", text)),
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Command::Spawn => write!(f, "g"),
+            Command::Move(ship_id, direction) => write!(f, "m {} {}", ship_id.0, direction.get_char_encoding()),
+            Command::Construct(ship_id) => write!(f, "c {}", ship_id.0),
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Command, String> {
+        Command::parse(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::command::Command;
+    use hlt::direction::Direction;
+    use hlt::ShipId;
+
+    #[test]
+    fn every_command_round_trips() {
+        let mut commands = vec![Command::Spawn, Command::Construct(ShipId(0)), Command::Construct(ShipId(117))];
+        let mut directions = Direction::get_all_cardinals();
+        directions.push(Direction::Still);
+        for direction in directions {
+            commands.push(Command::Move(ShipId(42), direction));
+        }
+
+        let expected = ["g", "c 0", "c 117", "m 42 n", "m 42 s", "m 42 e", "m 42 w", "m 42 o"];
+        for (command, text) in commands.iter().zip(expected.iter()) {
+            assert_eq!(command.to_string(), *text);
+            assert!(Command::parse(text) == Ok(*command));
+            assert!(text.parse::<Command>() == Ok(*command));
+        }
+    }
+
+    #[test]
+    fn extra_whitespace_is_allowed() {
+        assert!(Command::parse("  m   3\tn ") == Ok(Command::Move(ShipId(3), Direction::North)));
+    }
+
+    #[test]
+    fn malformed_commands_are_rejected() {
+        for text in &["", "x", "g 1", "c", "c -1", "c x", "c 1 2", "m 1", "m 1 x", "m 1 nn", "m x n", "m 1 n 2", "M 1 n"] {
+            assert!(Command::parse(text).is_err(), "'{}' parsed", text);
+            assert!(text.parse::<Command>().is_err());
+        }
+        assert_eq!(Command::parse("m 1 q").err(), Some("bad direction in command 'm 1 q'".to_string()));
+        assert_eq!(Command::parse("c 1.5").err(), Some("bad ship id '1.5' in command 'c 1.5'".to_string()));
     }
 }
@@ -1,7 +1,29 @@
+use hlt::json::Json;
 use hlt::log::Log;
-use std::collections::HashMap;
-use std::str::FromStr;
+use hlt::protocol_error::ProtocolError;
+use std::collections::BTreeMap;
 
+// Keys every official server sends that the typed fields don't cover, with the values
+// it sends them with. The map size keys are filled in by `official`.
+const OFFICIAL_EXTRAS: [(&str, &str); 12] = [
+    ("DROPOFF_PENALTY_RATIO", "4"),
+    ("FACTOR_EXP_1", "2.0"),
+    ("FACTOR_EXP_2", "2.0"),
+    ("INITIAL_ENERGY", "5000"),
+    ("MAX_CELL_PRODUCTION", "1000"),
+    ("MAX_PLAYERS", "16"),
+    ("MAX_TURN_THRESHOLD", "64"),
+    ("MIN_CELL_PRODUCTION", "900"),
+    ("MIN_TURNS", "400"),
+    ("MIN_TURN_THRESHOLD", "32"),
+    ("PERSISTENCE", "0.7"),
+    ("STRICT_ERRORS", "false"),
+];
+
+// The typed fields are what the bot and the engine use. `raw` holds every key of the
+// constants block as it was sent, so keys we have no field for are still there to look
+// at with `get`, and `to_json` writes them back out.
+#[derive(Clone)]
 pub struct Constants {
     pub max_halite: usize,
     pub ship_cost: usize,
@@ -15,52 +37,239 @@ pub struct Constants {
     pub inspired_extract_ratio: usize,
     pub inspired_bonus_multiplier: f64,
     pub inspired_move_cost_ratio: usize,
+    pub capture_enabled: bool,
+    pub capture_radius: usize,
+    pub ships_above_for_capture: usize,
+    pub raw: BTreeMap<String, Json>,
 }
 
 impl Constants {
-    pub fn new(string_from_engine: &str) -> Constants {
-        let token_iter = string_from_engine.split(|c| " {},:\"\r\n".contains(c));
-        let token_iter = token_iter.filter(|x| !x.is_empty());
-        let tokens: Vec<&str> = token_iter.collect();
+    pub fn new(string_from_engine: &str) -> Result<Constants, ProtocolError> {
+        let json = Json::parse(string_from_engine.trim()).map_err(ProtocolError::BadConstants)?;
+        Constants::from_json(&json)
+    }
 
-        if (tokens.len() % 2) != 0 {
-            Log::panic("Error: constants: expected even total number of key and value tokens from server.");
-        }
+    // Keys we have a field for but that are missing get the official default, with a
+    // warning in the log. A key with a value of the wrong type is an error.
+    pub fn from_json(json: &Json) -> Result<Constants, ProtocolError> {
+        let raw = match json {
+            Json::Object(map) => map.clone(),
+            _ => return Err(ProtocolError::BadConstants("expected a JSON object".to_string())),
+        };
+        let defaults = Constants::official(32);
+
+        Ok(Constants {
+            ship_cost: Constants::typed(&raw, "NEW_ENTITY_ENERGY_COST", defaults.ship_cost, Json::as_usize)?,
+            dropoff_cost: Constants::typed(&raw, "DROPOFF_COST", defaults.dropoff_cost, Json::as_usize)?,
+            max_halite: Constants::typed(&raw, "MAX_ENERGY", defaults.max_halite, Json::as_usize)?,
+            max_turns: Constants::typed(&raw, "MAX_TURNS", defaults.max_turns, Json::as_usize)?,
+            extract_ratio: Constants::typed(&raw, "EXTRACT_RATIO", defaults.extract_ratio, Json::as_usize)?,
+            move_cost_ratio: Constants::typed(&raw, "MOVE_COST_RATIO", defaults.move_cost_ratio, Json::as_usize)?,
+            inspiration_enabled: Constants::typed(
+                &raw,
+                "INSPIRATION_ENABLED",
+                defaults.inspiration_enabled,
+                Json::as_bool,
+            )?,
+            inspiration_radius: Constants::typed(
+                &raw,
+                "INSPIRATION_RADIUS",
+                defaults.inspiration_radius,
+                Json::as_usize,
+            )?,
+            inspiration_ship_count: Constants::typed(
+                &raw,
+                "INSPIRATION_SHIP_COUNT",
+                defaults.inspiration_ship_count,
+                Json::as_usize,
+            )?,
+            inspired_extract_ratio: Constants::typed(
+                &raw,
+                "INSPIRED_EXTRACT_RATIO",
+                defaults.inspired_extract_ratio,
+                Json::as_usize,
+            )?,
+            inspired_bonus_multiplier: Constants::typed(
+                &raw,
+                "INSPIRED_BONUS_MULTIPLIER",
+                defaults.inspired_bonus_multiplier,
+                Json::as_f64,
+            )?,
+            inspired_move_cost_ratio: Constants::typed(
+                &raw,
+                "INSPIRED_MOVE_COST_RATIO",
+                defaults.inspired_move_cost_ratio,
+                Json::as_usize,
+            )?,
+            capture_enabled: Constants::typed(&raw, "CAPTURE_ENABLED", defaults.capture_enabled, Json::as_bool)?,
+            capture_radius: Constants::typed(&raw, "CAPTURE_RADIUS", defaults.capture_radius, Json::as_usize)?,
+            ships_above_for_capture: Constants::typed(
+                &raw,
+                "SHIPS_ABOVE_FOR_CAPTURE",
+                defaults.ships_above_for_capture,
+                Json::as_usize,
+            )?,
+            raw,
+        })
+    }
 
-        let mut map = HashMap::new();
+    // What the official server sends for a square map of the given size.
+    pub fn official(map_size: usize) -> Constants {
+        let max_turns = 400 + (map_size.clamp(32, 64) - 32) * 100 / 32;
 
-        for i in (0..tokens.len()).step_by(2) {
-            map.insert(tokens[i].to_string(), tokens[i+1].to_string());
-        }
+        let mut raw: BTreeMap<String, Json> = OFFICIAL_EXTRAS
+            .iter()
+            .map(|(key, value)| (key.to_string(), Json::parse(value).unwrap()))
+            .collect();
+        raw.insert("DEFAULT_MAP_HEIGHT".to_string(), Json::Int(map_size as i64));
+        raw.insert("DEFAULT_MAP_WIDTH".to_string(), Json::Int(map_size as i64));
 
-        Constants {
-            ship_cost: Constants::get_value(&map, "NEW_ENTITY_ENERGY_COST"),
-            dropoff_cost: Constants::get_value(&map, "DROPOFF_COST"),
-            max_halite: Constants::get_value(&map, "MAX_ENERGY"),
-            max_turns: Constants::get_value(&map, "MAX_TURNS"),
-            extract_ratio: Constants::get_value(&map, "EXTRACT_RATIO"),
-            move_cost_ratio: Constants::get_value(&map, "MOVE_COST_RATIO"),
-            inspiration_enabled: Constants::get_value(&map, "INSPIRATION_ENABLED"),
-            inspiration_radius: Constants::get_value(&map, "INSPIRATION_RADIUS"),
-            inspiration_ship_count: Constants::get_value(&map, "INSPIRATION_SHIP_COUNT"),
-            inspired_extract_ratio: Constants::get_value(&map, "INSPIRED_EXTRACT_RATIO"),
-            inspired_bonus_multiplier: Constants::get_value(&map, "INSPIRED_BONUS_MULTIPLIER"),
-            inspired_move_cost_ratio: Constants::get_value(&map, "INSPIRED_MOVE_COST_RATIO"),
+        let mut constants = Constants {
+            ship_cost: 1000,
+            dropoff_cost: 4000,
+            max_halite: 1000,
+            max_turns,
+            extract_ratio: 4,
+            move_cost_ratio: 10,
+            inspiration_enabled: true,
+            inspiration_radius: 4,
+            inspiration_ship_count: 2,
+            inspired_extract_ratio: 4,
+            inspired_bonus_multiplier: 2.0,
+            inspired_move_cost_ratio: 10,
+            capture_enabled: false,
+            capture_radius: 3,
+            ships_above_for_capture: 3,
+            raw: BTreeMap::new(),
+        };
+        if let Json::Object(typed) = constants.to_json() {
+            raw.extend(typed);
         }
+        constants.raw = raw;
+        constants
+    }
+
+    // Any key of the constants block, including the ones without a typed field.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        self.raw.get(key)
     }
 
-    fn get_value<T: FromStr>(map: &HashMap<String, String>, key: &str) -> T {
-        let s = Constants::get_string(map, key);
-        match s.parse::<T>() {
-            Ok(x) => x,
-            Err(_) => Log::panic(&format!("Error: constants: for {} got '{}' from server and failed to parse that.", key, s))
+    // Keys that were sent but have no typed field.
+    pub fn extra_keys(&self) -> Vec<&str> {
+        let typed = self.typed_json();
+        self.raw.keys().filter(|key| typed.get(key).is_none()).map(|key| key.as_str()).collect()
+    }
+
+    // Every key that was sent, with the typed fields as they are now.
+    pub fn to_json(&self) -> Json {
+        let mut map = self.raw.clone();
+        if let Json::Object(typed) = self.typed_json() {
+            map.extend(typed);
         }
+        Json::Object(map)
+    }
+
+    fn typed_json(&self) -> Json {
+        Json::object()
+            .with("NEW_ENTITY_ENERGY_COST", Json::Int(self.ship_cost as i64))
+            .with("DROPOFF_COST", Json::Int(self.dropoff_cost as i64))
+            .with("MAX_ENERGY", Json::Int(self.max_halite as i64))
+            .with("MAX_TURNS", Json::Int(self.max_turns as i64))
+            .with("EXTRACT_RATIO", Json::Int(self.extract_ratio as i64))
+            .with("MOVE_COST_RATIO", Json::Int(self.move_cost_ratio as i64))
+            .with("INSPIRATION_ENABLED", Json::Bool(self.inspiration_enabled))
+            .with("INSPIRATION_RADIUS", Json::Int(self.inspiration_radius as i64))
+            .with("INSPIRATION_SHIP_COUNT", Json::Int(self.inspiration_ship_count as i64))
+            .with("INSPIRED_EXTRACT_RATIO", Json::Int(self.inspired_extract_ratio as i64))
+            .with("INSPIRED_BONUS_MULTIPLIER", Json::Float(self.inspired_bonus_multiplier))
+            .with("INSPIRED_MOVE_COST_RATIO", Json::Int(self.inspired_move_cost_ratio as i64))
+            .with("CAPTURE_ENABLED", Json::Bool(self.capture_enabled))
+            .with("CAPTURE_RADIUS", Json::Int(self.capture_radius as i64))
+            .with("SHIPS_ABOVE_FOR_CAPTURE", Json::Int(self.ships_above_for_capture as i64))
     }
 
-    fn get_string<'a>(map: &'a HashMap<String, String>, key: &str) -> &'a String {
-        match map.get(key) {
-            Some(x) => x,
-            None => Log::panic(&format!("Error: constants: server did not send {} constant.", key))
+    fn typed<T: ToString>(
+        raw: &BTreeMap<String, Json>,
+        key: &str,
+        default: T,
+        convert: fn(&Json) -> Option<T>,
+    ) -> Result<T, ProtocolError> {
+        match raw.get(key) {
+            Some(value) => convert(value).ok_or_else(|| ProtocolError::BadConstant {
+                key: key.to_string(),
+                value: value.to_string(),
+            }),
+            None => {
+                Log::log(&format!(
+                    "Warning: constants: server did not send {}, using {}.",
+                    key,
+                    default.to_string()
+                ));
+                Ok(default)
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use hlt::constants::Constants;
+    use hlt::json::Json;
+    use hlt::protocol_error::ProtocolError;
+
+    #[test]
+    fn official_max_turns_32() {
+        assert_eq!(Constants::official(32).max_turns, 400);
+    }
+
+    #[test]
+    fn official_max_turns_40() {
+        assert_eq!(Constants::official(40).max_turns, 425);
+    }
+
+    #[test]
+    fn official_max_turns_48() {
+        assert_eq!(Constants::official(48).max_turns, 450);
+    }
+
+    #[test]
+    fn official_max_turns_56() {
+        assert_eq!(Constants::official(56).max_turns, 475);
+    }
+
+    #[test]
+    fn official_max_turns_64() {
+        assert_eq!(Constants::official(64).max_turns, 500);
+    }
+
+    #[test]
+    fn missing_keys_fall_back_to_the_defaults() {
+        let constants = Constants::new(r#"{"MAX_TURNS":10,"EXTRACT_RATIO":3}"#).unwrap();
+        assert_eq!(constants.max_turns, 10);
+        assert_eq!(constants.extract_ratio, 3);
+        assert_eq!(constants.ship_cost, 1000);
+        assert_eq!(constants.inspired_bonus_multiplier, 2.0);
+        assert!(!constants.capture_enabled);
+        assert!(constants.extra_keys().is_empty());
+    }
+
+    #[test]
+    fn a_mistyped_key_is_an_error() {
+        let error = Constants::new(r#"{"MAX_TURNS":"ten"}"#).err().unwrap();
+        assert_eq!(error, ProtocolError::BadConstant { key: "MAX_TURNS".to_string(), value: "\"ten\"".to_string() });
+        assert!(Constants::new(r#"{"CAPTURE_ENABLED":1}"#).is_err());
+        assert!(Constants::new(r#"{"MAX_ENERGY":-1}"#).is_err());
+    }
+
+    #[test]
+    fn unknown_keys_are_kept_and_written_back() {
+        let constants = Constants::new(r#"{"MAX_TURNS":10,"PERSISTENCE":0.7,"SOMETHING_NEW":[1]}"#).unwrap();
+        assert_eq!(constants.extra_keys(), vec!["PERSISTENCE", "SOMETHING_NEW"]);
+        assert_eq!(constants.get("SOMETHING_NEW"), Some(&Json::Array(vec![Json::Int(1)])));
+
+        let written = constants.to_json();
+        assert_eq!(written.get("PERSISTENCE"), Some(&Json::Float(0.7)));
+        assert_eq!(written.get("MAX_TURNS"), Some(&Json::Int(10)));
+        assert_eq!(Constants::from_json(&written).unwrap().extra_keys(), vec!["PERSISTENCE", "SOMETHING_NEW"]);
+    }
+}
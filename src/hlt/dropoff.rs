@@ -3,7 +3,9 @@ use hlt::entity::Entity;
 use hlt::input::Input;
 use hlt::PlayerId;
 use hlt::position::Position;
+use hlt::protocol_error::ProtocolError;
 
+#[derive(Clone)]
 pub struct Dropoff {
     pub owner: PlayerId,
     pub id: DropoffId,
@@ -21,12 +23,12 @@ impl Entity for Dropoff {
 }
 
 impl Dropoff {
-    pub fn generate(input: &mut Input, player_id: PlayerId) -> Dropoff {
-        input.read_and_parse_line();
-        let id = DropoffId(input.next_usize());
-        let x = input.next_i32();
-        let y = input.next_i32();
+    pub fn generate(input: &mut Input, player_id: PlayerId) -> Result<Dropoff, ProtocolError> {
+        input.read_and_parse_line()?;
+        let id = DropoffId(input.next_usize()?);
+        let x = input.next_i32()?;
+        let y = input.next_i32()?;
 
-        Dropoff { owner: player_id, id, position: Position { x, y } }
+        Ok(Dropoff { owner: player_id, id, position: Position { x, y } })
     }
 }
@@ -0,0 +1,586 @@
+use hlt::command::Command;
+use hlt::direction::Direction;
+use hlt::dropoff::Dropoff;
+use hlt::game::Game;
+use hlt::map_cell::Structure;
+use hlt::position::Position;
+use hlt::ship::Ship;
+use hlt::DropoffId;
+use hlt::PlayerId;
+use hlt::ShipId;
+use std::cmp::min;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+pub struct Collision {
+    pub position: Position,
+    pub ship_ids: Vec<ShipId>,
+    pub halite: usize,
+}
+
+pub struct TurnResult {
+    pub spawned: Vec<ShipId>,
+    pub constructed: Vec<DropoffId>,
+    pub collisions: Vec<Collision>,
+    pub captured: Vec<(ShipId, PlayerId)>,
+    pub stalled: Vec<ShipId>,
+    pub deposited: Vec<usize>,
+    pub mined: Vec<usize>,
+    pub errors: Vec<(PlayerId, String)>,
+}
+
+impl TurnResult {
+    fn new(num_players: usize) -> TurnResult {
+        TurnResult {
+            spawned: Vec::new(),
+            constructed: Vec::new(),
+            collisions: Vec::new(),
+            captured: Vec::new(),
+            stalled: Vec::new(),
+            deposited: vec![0; num_players],
+            mined: vec![0; num_players],
+            errors: Vec::new(),
+        }
+    }
+}
+
+// Resolves turns on a Game the way the official engine does. Invalid commands are
+// dropped and reported in TurnResult::errors instead of eliminating the player.
+// Game::turn_number is the turn being played, so it runs from 1 to max_turns.
+pub struct Engine {
+    next_ship_id: usize,
+    next_dropoff_id: usize,
+}
+
+impl Engine {
+    pub fn new(game: &Game) -> Engine {
+        let next_ship_id = game.ships.keys().map(|id| id.0 + 1).max().unwrap_or(0);
+        let next_dropoff_id = game.dropoffs.keys().map(|id| id.0 + 1).max().unwrap_or(0);
+
+        Engine { next_ship_id, next_dropoff_id }
+    }
+
+    // For carrying on a game whose newest ships may already be gone.
+    pub fn resume(next_ship_id: usize, next_dropoff_id: usize) -> Engine {
+        Engine { next_ship_id, next_dropoff_id }
+    }
+
+    pub fn next_ids(&self) -> (usize, usize) {
+        (self.next_ship_id, self.next_dropoff_id)
+    }
+
+    pub fn is_over(game: &Game) -> bool {
+        game.turn_number > game.constants.max_turns
+    }
+
+    pub fn move_cost(cell_halite: usize, move_cost_ratio: usize) -> usize {
+        cell_halite / move_cost_ratio
+    }
+
+    pub fn extraction(cell_halite: usize, extract_ratio: usize) -> usize {
+        cell_halite.div_ceil(extract_ratio)
+    }
+
+    // What it costs the ship to leave its cell, given the ships inspired this turn.
+    pub fn ship_move_cost(game: &Game, inspired: &HashSet<ShipId>, ship: &Ship) -> usize {
+        let ratio = if inspired.contains(&ship.id) {
+            game.constants.inspired_move_cost_ratio
+        } else {
+            game.constants.move_cost_ratio
+        };
+        Engine::move_cost(game.map.at_position(&ship.position).halite, ratio)
+    }
+
+    // What the server does to a player whose bot crashed or timed out: its ships leave
+    // the map, cargo and all. Its bank and structures stay for the final ranking.
+    pub fn remove_ships(game: &mut Game, player_id: PlayerId) {
+        for ship_id in game.players[player_id.0].ship_ids.drain(..) {
+            game.ships.remove(&ship_id);
+        }
+    }
+
+    pub fn structure_owner(game: &Game, position: &Position) -> Option<PlayerId> {
+        match game.map.at_position(position).structure {
+            Structure::Shipyard(player_id) => Some(player_id),
+            Structure::Dropoff(dropoff_id) => game.dropoffs.get(&dropoff_id).map(|x| x.owner),
+            Structure::None => None,
+        }
+    }
+
+    pub fn inspired_ships(game: &Game) -> HashSet<ShipId> {
+        let mut inspired = HashSet::new();
+        if !game.constants.inspiration_enabled {
+            return inspired;
+        }
+
+        for ship in game.ships.values() {
+            let nearby_enemies = game
+                .ships
+                .values()
+                .filter(|other| {
+                    other.owner != ship.owner
+                        && game.map.calculate_distance(&ship.position, &other.position)
+                            <= game.constants.inspiration_radius
+                })
+                .count();
+
+            if nearby_enemies >= game.constants.inspiration_ship_count {
+                inspired.insert(ship.id);
+            }
+        }
+        inspired
+    }
+
+    // Number of ships each player has within `radius` of the position.
+    pub fn ships_within(game: &Game, position: &Position, radius: usize) -> Vec<usize> {
+        let mut counts = vec![0; game.players.len()];
+        for ship in game.ships.values() {
+            if game.map.calculate_distance(position, &ship.position) <= radius {
+                counts[ship.owner.0] += 1;
+            }
+        }
+        counts
+    }
+
+    // The player that would take over a ship of `owner` given the ship counts around it:
+    // whoever has strictly the most ships there, if that beats the owner's count (which
+    // includes the ship itself) by at least SHIPS_ABOVE_FOR_CAPTURE.
+    pub fn capturer(counts: &[usize], owner: PlayerId, ships_above_for_capture: usize) -> Option<PlayerId> {
+        let most = counts.iter().cloned().max().unwrap_or(0);
+        let leaders: Vec<usize> = (0..counts.len()).filter(|i| counts[*i] == most).collect();
+        if leaders.len() != 1 || leaders[0] == owner.0 {
+            return None;
+        }
+        if most >= counts[owner.0] + ships_above_for_capture {
+            Some(PlayerId(leaders[0]))
+        } else {
+            None
+        }
+    }
+
+    pub fn resolve_turn(&mut self, game: &mut Game, commands: &[Vec<Command>]) -> TurnResult {
+        let mut result = TurnResult::new(game.players.len());
+        let inspired = Engine::inspired_ships(game);
+        game.map.begin_turn();
+
+        let mut spawns: Vec<PlayerId> = Vec::new();
+        let mut constructs: Vec<ShipId> = Vec::new();
+        let mut moves: HashMap<ShipId, Direction> = HashMap::new();
+
+        for (player_index, player_commands) in commands.iter().enumerate().take(game.players.len()) {
+            let player_id = PlayerId(player_index);
+            let mut commanded: HashSet<ShipId> = HashSet::new();
+            let mut has_spawned = false;
+
+            for command in player_commands {
+                let ship_id = match *command {
+                    Command::Spawn => {
+                        if has_spawned {
+                            result.errors.push((player_id, "spawned more than once".to_string()));
+                        } else {
+                            has_spawned = true;
+                            spawns.push(player_id);
+                        }
+                        continue;
+                    }
+                    Command::Construct(ship_id) | Command::Move(ship_id, _) => ship_id,
+                };
+
+                let owned = game.ships.get(&ship_id).is_some_and(|ship| ship.owner == player_id);
+                if !owned {
+                    result.errors.push((player_id, format!("commanded ship {} it does not own", ship_id.0)));
+                    continue;
+                }
+                if !commanded.insert(ship_id) {
+                    result.errors.push((player_id, format!("commanded ship {} more than once", ship_id.0)));
+                    continue;
+                }
+
+                match *command {
+                    Command::Construct(_) => constructs.push(ship_id),
+                    Command::Move(_, direction) => {
+                        moves.insert(ship_id, direction);
+                    }
+                    Command::Spawn => (),
+                }
+            }
+        }
+
+        for ship_id in constructs {
+            self.construct(game, ship_id, &mut result);
+        }
+
+        for player_id in spawns {
+            if game.players[player_id.0].halite >= game.constants.ship_cost {
+                game.players[player_id.0].halite -= game.constants.ship_cost;
+            } else {
+                result.errors.push((player_id, "not enough halite to spawn".to_string()));
+                continue;
+            }
+
+            let ship_id = ShipId(self.next_ship_id);
+            self.next_ship_id += 1;
+
+            let position = game.players[player_id.0].shipyard.position;
+            let ship = Ship::new(player_id, ship_id, position, 0, game.constants.max_halite);
+            game.players[player_id.0].ship_ids.push(ship_id);
+            game.ships.insert(ship_id, ship);
+            result.spawned.push(ship_id);
+        }
+
+        let mut still: Vec<ShipId> = Vec::new();
+        for player in &game.players {
+            for ship_id in &player.ship_ids {
+                if result.spawned.contains(ship_id) {
+                    continue;
+                }
+
+                let direction = moves.get(ship_id).cloned().unwrap_or(Direction::Still);
+                if direction == Direction::Still {
+                    still.push(*ship_id);
+                    continue;
+                }
+
+                let cost = Engine::ship_move_cost(game, &inspired, &game.ships[ship_id]);
+                let ship = game.ships.get_mut(ship_id).unwrap();
+                if ship.halite < cost {
+                    result.stalled.push(*ship_id);
+                    still.push(*ship_id);
+                    continue;
+                }
+
+                ship.halite -= cost;
+                ship.position = game.map.normalize(&ship.position.directional_offset(direction));
+            }
+        }
+
+        Engine::resolve_collisions(game, &mut result);
+        Engine::resolve_captures(game, &mut result);
+        Engine::mine(game, &still, &inspired, &mut result);
+        Engine::deposit(game, &mut result);
+
+        game.turn_number += 1;
+        result
+    }
+
+    fn construct(&mut self, game: &mut Game, ship_id: ShipId, result: &mut TurnResult) {
+        let (owner, position, ship_halite) = {
+            let ship = &game.ships[&ship_id];
+            (ship.owner, ship.position, ship.halite)
+        };
+
+        if game.map.at_position(&position).structure.is_some() {
+            result.errors.push((owner, format!("ship {} cannot construct on a structure", ship_id.0)));
+            return;
+        }
+
+        let cell_halite = game.map.at_position(&position).halite;
+        let available = game.players[owner.0].halite + ship_halite + cell_halite;
+        if available < game.constants.dropoff_cost {
+            result.errors.push((owner, format!("not enough halite for ship {} to construct", ship_id.0)));
+            return;
+        }
+
+        let dropoff_id = DropoffId(self.next_dropoff_id);
+        self.next_dropoff_id += 1;
+
+        game.players[owner.0].halite = available - game.constants.dropoff_cost;
+        game.players[owner.0].ship_ids.retain(|x| *x != ship_id);
+        game.players[owner.0].dropoff_ids.push(dropoff_id);
+        game.ships.remove(&ship_id);
+        game.dropoffs.insert(dropoff_id, Dropoff { owner, id: dropoff_id, position });
+
+        game.map.clear_halite(&position);
+        game.map.at_position_mut(&position).structure = Structure::Dropoff(dropoff_id);
+
+        result.constructed.push(dropoff_id);
+    }
+
+    fn resolve_collisions(game: &mut Game, result: &mut TurnResult) {
+        let mut occupants: HashMap<Position, Vec<ShipId>> = HashMap::new();
+        let mut order: Vec<Position> = Vec::new();
+
+        for player in &game.players {
+            for ship_id in &player.ship_ids {
+                let position = game.ships[ship_id].position;
+                let ships = occupants.entry(position).or_default();
+                if ships.is_empty() {
+                    order.push(position);
+                }
+                ships.push(*ship_id);
+            }
+        }
+
+        for position in order {
+            let ship_ids = &occupants[&position];
+            if ship_ids.len() < 2 {
+                continue;
+            }
+
+            let mut halite = 0;
+            for ship_id in ship_ids {
+                let ship = game.ships.remove(ship_id).unwrap();
+                game.players[ship.owner.0].ship_ids.retain(|x| x != ship_id);
+                halite += ship.halite;
+            }
+
+            match Engine::structure_owner(game, &position) {
+                Some(owner) => game.players[owner.0].halite += halite,
+                None => {
+                    let cell_halite = game.map.at_position(&position).halite;
+                    game.map.set_halite(&position, cell_halite + halite);
+                }
+            }
+
+            result.collisions.push(Collision { position, ship_ids: ship_ids.clone(), halite });
+        }
+    }
+
+    // Captures are decided on the positions after collisions and all applied at once.
+    fn resolve_captures(game: &mut Game, result: &mut TurnResult) {
+        if !game.constants.capture_enabled {
+            return;
+        }
+
+        let mut captures: Vec<(ShipId, PlayerId)> = Vec::new();
+        for player in &game.players {
+            for ship_id in &player.ship_ids {
+                let ship = &game.ships[ship_id];
+                let counts = Engine::ships_within(game, &ship.position, game.constants.capture_radius);
+                if let Some(capturer) = Engine::capturer(&counts, ship.owner, game.constants.ships_above_for_capture) {
+                    captures.push((*ship_id, capturer));
+                }
+            }
+        }
+
+        for (ship_id, capturer) in &captures {
+            let ship = game.ships.get_mut(ship_id).unwrap();
+            game.players[ship.owner.0].ship_ids.retain(|x| x != ship_id);
+            game.players[capturer.0].ship_ids.push(*ship_id);
+            ship.owner = *capturer;
+        }
+        result.captured = captures;
+    }
+
+    fn mine(game: &mut Game, still: &[ShipId], inspired: &HashSet<ShipId>, result: &mut TurnResult) {
+        for ship_id in still {
+            let ship = match game.ships.get_mut(ship_id) {
+                Some(ship) => ship,
+                None => continue,
+            };
+
+            let ratio = if inspired.contains(ship_id) {
+                game.constants.inspired_extract_ratio
+            } else {
+                game.constants.extract_ratio
+            };
+
+            let capacity = game.constants.max_halite.saturating_sub(ship.halite);
+            let cell_halite = game.map.at_position(&ship.position).halite;
+            let extracted = min(Engine::extraction(cell_halite, ratio), capacity);
+            game.map.set_halite(&ship.position, cell_halite - extracted);
+            ship.halite += extracted;
+
+            if inspired.contains(ship_id) {
+                let bonus = (extracted as f64 * game.constants.inspired_bonus_multiplier) as usize;
+                ship.halite += min(bonus, capacity - extracted);
+            }
+
+            result.mined[ship.owner.0] += extracted;
+        }
+    }
+
+    fn deposit(game: &mut Game, result: &mut TurnResult) {
+        for player_index in 0..game.players.len() {
+            for ship_id in game.players[player_index].ship_ids.clone() {
+                let position = game.ships[&ship_id].position;
+                if Engine::structure_owner(game, &position) != Some(PlayerId(player_index)) {
+                    continue;
+                }
+
+                let ship = game.ships.get_mut(&ship_id).unwrap();
+                game.players[player_index].halite += ship.halite;
+                result.deposited[player_index] += ship.halite;
+                ship.halite = 0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::command::Command;
+    use hlt::direction::Direction;
+    use hlt::engine::Engine;
+    use hlt::map_cell::Structure;
+    use hlt::test_game::*;
+    use hlt::DropoffId;
+    use hlt::ShipId;
+
+    const YARDS: [(i32, i32); 2] = [(2, 2), (13, 13)];
+
+    fn move_ship(id: usize, direction: Direction) -> Command {
+        Command::Move(ShipId(id), direction)
+    }
+
+    #[test]
+    fn move_cost_rounds_down_and_extraction_rounds_up() {
+        let mut game = game(16, &YARDS);
+        put_halite(&mut game, 5, 5, 99);
+        put_halite(&mut game, 5, 8, 99);
+        add_ship(&mut game, 0, 0, 5, 5, 9);
+        add_ship(&mut game, 0, 1, 5, 8, 8);
+
+        let commands = vec![vec![move_ship(0, Direction::East), move_ship(1, Direction::East)], vec![]];
+        let result = Engine::new(&game).resolve_turn(&mut game, &commands);
+
+        // 99 / 10 = 9 to move, so the first ship leaves with nothing and the second stalls.
+        assert!(game.ships[&ShipId(0)].position == pos(6, 5));
+        assert_eq!(game.ships[&ShipId(0)].halite, 0);
+        assert!(result.stalled == vec![ShipId(1)]);
+        assert!(game.ships[&ShipId(1)].position == pos(5, 8));
+
+        // A stalled ship mines: 99 / 4 rounded up.
+        assert_eq!(game.ships[&ShipId(1)].halite, 8 + 25);
+        assert_eq!(game.map.at_position(&pos(5, 8)).halite, 74);
+        assert_eq!(result.mined, vec![25, 0]);
+    }
+
+    #[test]
+    fn two_enemies_within_the_radius_inspire_a_ship() {
+        let mut game = game(16, &YARDS);
+        add_ship(&mut game, 0, 0, 5, 5, 0);
+        add_ship(&mut game, 1, 1, 5, 7, 0);
+        add_ship(&mut game, 1, 2, 7, 7, 0);
+
+        Engine::new(&game).resolve_turn(&mut game, &[vec![], vec![]]);
+
+        // 25 mined plus twice that as the bonus, which does not come off the cell.
+        assert_eq!(game.ships[&ShipId(0)].halite, 75);
+        assert_eq!(game.map.at_position(&pos(5, 5)).halite, 75);
+        // Each enemy has only one ship of player 0 near it.
+        assert_eq!(game.ships[&ShipId(1)].halite, 25);
+    }
+
+    #[test]
+    fn one_enemy_within_the_radius_does_not_inspire() {
+        let mut game = game(16, &YARDS);
+        add_ship(&mut game, 0, 0, 5, 5, 0);
+        add_ship(&mut game, 1, 1, 5, 7, 0);
+        add_ship(&mut game, 1, 2, 8, 7, 0);
+
+        Engine::new(&game).resolve_turn(&mut game, &[vec![], vec![]]);
+
+        assert_eq!(game.ships[&ShipId(0)].halite, 25);
+    }
+
+    #[test]
+    fn colliding_ships_drop_their_cargo_on_the_cell() {
+        let mut game = game(16, &YARDS);
+        add_ship(&mut game, 0, 0, 3, 4, 100);
+        add_ship(&mut game, 1, 1, 4, 5, 50);
+
+        let commands = vec![vec![move_ship(0, Direction::South)], vec![move_ship(1, Direction::West)]];
+        let result = Engine::new(&game).resolve_turn(&mut game, &commands);
+
+        // Both pay 10 to move, so 90 + 40 lands on a cell of 100.
+        assert!(game.ships.is_empty());
+        assert_eq!(result.collisions.len(), 1);
+        assert_eq!(result.collisions[0].halite, 130);
+        assert_eq!(game.map.at_position(&pos(3, 5)).halite, 230);
+        assert_eq!(game.players[0].halite, 5000);
+        assert_eq!(game.players[1].halite, 5000);
+    }
+
+    #[test]
+    fn colliding_ships_on_a_structure_pay_its_owner() {
+        let mut game = game(16, &YARDS);
+        put_halite(&mut game, 6, 6, 0);
+        add_dropoff(&mut game, 0, 0, 6, 6);
+        add_ship(&mut game, 0, 0, 6, 5, 100);
+        add_ship(&mut game, 1, 1, 7, 6, 50);
+
+        let commands = vec![vec![move_ship(0, Direction::South)], vec![move_ship(1, Direction::West)]];
+        Engine::new(&game).resolve_turn(&mut game, &commands);
+
+        assert!(game.ships.is_empty());
+        assert_eq!(game.players[0].halite, 5130);
+        assert_eq!(game.players[1].halite, 5000);
+        assert_eq!(game.map.at_position(&pos(6, 6)).halite, 0);
+    }
+
+    #[test]
+    fn construction_counts_the_cargo_and_the_cell() {
+        let mut game = game(16, &YARDS);
+        game.players[0].halite = 3600;
+        put_halite(&mut game, 5, 5, 200);
+        add_ship(&mut game, 0, 0, 5, 5, 300);
+
+        let result = Engine::new(&game).resolve_turn(&mut game, &[vec![Command::Construct(ShipId(0))], vec![]]);
+
+        assert!(result.errors.is_empty());
+        assert!(result.constructed == vec![DropoffId(0)]);
+        assert_eq!(game.players[0].halite, 3600 + 300 + 200 - 4000);
+        assert!(game.ships.is_empty());
+        assert!(game.players[0].ship_ids.is_empty());
+        assert_eq!(game.map.at_position(&pos(5, 5)).halite, 0);
+        assert!(game.map.at_position(&pos(5, 5)).structure == Structure::Dropoff(DropoffId(0)));
+    }
+
+    #[test]
+    fn construction_short_of_the_cost_is_refused() {
+        let mut game = game(16, &YARDS);
+        game.players[0].halite = 3400;
+        put_halite(&mut game, 5, 5, 200);
+        add_ship(&mut game, 0, 0, 5, 5, 300);
+
+        let result = Engine::new(&game).resolve_turn(&mut game, &[vec![Command::Construct(ShipId(0))], vec![]]);
+
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.constructed.is_empty());
+        assert!(game.dropoffs.is_empty());
+        assert_eq!(game.players[0].halite, 3400);
+        // The ship was left where it was, so it mined instead.
+        assert_eq!(game.ships[&ShipId(0)].halite, 350);
+        assert_eq!(game.map.at_position(&pos(5, 5)).halite, 150);
+    }
+
+    #[test]
+    fn spawning_onto_a_ship_on_the_shipyard_destroys_both() {
+        let mut game = game(16, &YARDS);
+        add_ship(&mut game, 0, 0, 2, 2, 50);
+
+        let result = Engine::new(&game).resolve_turn(&mut game, &[vec![Command::Spawn], vec![]]);
+
+        assert!(result.spawned == vec![ShipId(1)]);
+        assert_eq!(result.collisions.len(), 1);
+        assert!(game.ships.is_empty());
+        // The ship is paid for and the wreck's cargo goes to the shipyard's owner.
+        assert_eq!(game.players[0].halite, 5000 - 1000 + 50);
+    }
+
+    #[test]
+    fn spawning_after_the_ship_leaves_the_shipyard_succeeds() {
+        let mut game = game(16, &YARDS);
+        add_ship(&mut game, 0, 0, 2, 2, 50);
+
+        let commands = vec![vec![Command::Spawn, move_ship(0, Direction::East)], vec![]];
+        let result = Engine::new(&game).resolve_turn(&mut game, &commands);
+
+        assert!(result.collisions.is_empty());
+        assert_eq!(game.ships.len(), 2);
+        assert!(game.ships[&ShipId(1)].position == pos(2, 2));
+        assert_eq!(game.players[0].halite, 4000);
+    }
+
+    #[test]
+    fn the_game_is_over_once_the_last_turn_is_played() {
+        let mut game = game(16, &YARDS);
+        game.turn_number = game.constants.max_turns;
+        assert!(!Engine::is_over(&game));
+
+        Engine::new(&game).resolve_turn(&mut game, &[vec![], vec![]]);
+        assert_eq!(game.turn_number, game.constants.max_turns + 1);
+        assert!(Engine::is_over(&game));
+    }
+}
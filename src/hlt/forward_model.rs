@@ -0,0 +1,489 @@
+use hlt::command::Command;
+use hlt::direction::Direction;
+use hlt::engine::Engine;
+use hlt::game::Game;
+use hlt::map_cell::Structure;
+use hlt::position::Position;
+use hlt::PlayerId;
+use hlt::ShipId;
+use std::cmp::min;
+
+#[derive(Copy, Clone)]
+pub struct Rules {
+    pub max_halite: usize,
+    pub ship_cost: usize,
+    pub dropoff_cost: usize,
+    pub extract_ratio: usize,
+    pub move_cost_ratio: usize,
+    pub inspiration_enabled: bool,
+    pub inspiration_radius: usize,
+    pub inspiration_ship_count: usize,
+    pub inspired_extract_ratio: usize,
+    pub inspired_bonus_multiplier: f64,
+    pub inspired_move_cost_ratio: usize,
+    pub capture_enabled: bool,
+    pub capture_radius: usize,
+    pub ships_above_for_capture: usize,
+}
+
+#[derive(Copy, Clone)]
+pub struct SimShip {
+    pub id: ShipId,
+    pub owner: PlayerId,
+    pub position: Position,
+    pub halite: usize,
+}
+
+#[derive(Clone)]
+struct Undo {
+    halite: Vec<(usize, u32)>,
+    structures: Vec<(usize, u8)>,
+    ships: Vec<SimShip>,
+    banks: Vec<usize>,
+    next_ship_id: usize,
+}
+
+// Compact copy of a Game for look-ahead search. Cells are flat vectors indexed by
+// y * width + x, structures store their owner + 1 (0 is no structure) and ships are
+// kept sorted by id. step applies the same rules as Engine::resolve_turn and records
+// what it changed so undo can put it back without cloning the whole state.
+#[derive(Clone)]
+pub struct ForwardModel {
+    pub width: usize,
+    pub height: usize,
+    pub turn_number: usize,
+    pub rules: Rules,
+    pub halite: Vec<u32>,
+    pub structures: Vec<u8>,
+    pub ships: Vec<SimShip>,
+    pub banks: Vec<usize>,
+    pub shipyards: Vec<Position>,
+    next_ship_id: usize,
+    history: Vec<Undo>,
+}
+
+impl ForwardModel {
+    pub fn from_game(game: &Game) -> ForwardModel {
+        let width = game.map.width;
+        let height = game.map.height;
+
+        let mut halite: Vec<u32> = Vec::with_capacity(width * height);
+        let mut structures: Vec<u8> = Vec::with_capacity(width * height);
+        for row in &game.map.cells {
+            for cell in row {
+                halite.push(cell.halite as u32);
+                let owner = match cell.structure {
+                    Structure::Shipyard(player_id) => Some(player_id),
+                    Structure::Dropoff(dropoff_id) => game.dropoffs.get(&dropoff_id).map(|x| x.owner),
+                    Structure::None => None,
+                };
+                structures.push(owner.map_or(0, |x| x.0 as u8 + 1));
+            }
+        }
+
+        let mut ships: Vec<SimShip> = game
+            .ships
+            .values()
+            .map(|ship| SimShip { id: ship.id, owner: ship.owner, position: ship.position, halite: ship.halite })
+            .collect();
+        ships.sort_by_key(|ship| ship.id.0);
+
+        let constants = &game.constants;
+        let rules = Rules {
+            max_halite: constants.max_halite,
+            ship_cost: constants.ship_cost,
+            dropoff_cost: constants.dropoff_cost,
+            extract_ratio: constants.extract_ratio,
+            move_cost_ratio: constants.move_cost_ratio,
+            inspiration_enabled: constants.inspiration_enabled,
+            inspiration_radius: constants.inspiration_radius,
+            inspiration_ship_count: constants.inspiration_ship_count,
+            inspired_extract_ratio: constants.inspired_extract_ratio,
+            inspired_bonus_multiplier: constants.inspired_bonus_multiplier,
+            inspired_move_cost_ratio: constants.inspired_move_cost_ratio,
+            capture_enabled: constants.capture_enabled,
+            capture_radius: constants.capture_radius,
+            ships_above_for_capture: constants.ships_above_for_capture,
+        };
+
+        ForwardModel {
+            width,
+            height,
+            turn_number: game.turn_number,
+            rules,
+            halite,
+            structures,
+            next_ship_id: game.ships.keys().map(|id| id.0 + 1).max().unwrap_or(0),
+            ships,
+            banks: game.players.iter().map(|player| player.halite).collect(),
+            shipyards: game.players.iter().map(|player| player.shipyard.position).collect(),
+            history: Vec::new(),
+        }
+    }
+
+    pub fn index(&self, position: &Position) -> usize {
+        let width = self.width as i32;
+        let height = self.height as i32;
+        let x = ((position.x % width) + width) % width;
+        let y = ((position.y % height) + height) % height;
+        (y * width + x) as usize
+    }
+
+    pub fn normalize(&self, position: &Position) -> Position {
+        let index = self.index(position);
+        Position { x: (index % self.width) as i32, y: (index / self.width) as i32 }
+    }
+
+    pub fn halite_at(&self, position: &Position) -> usize {
+        self.halite[self.index(position)] as usize
+    }
+
+    pub fn structure_owner(&self, position: &Position) -> Option<PlayerId> {
+        match self.structures[self.index(position)] {
+            0 => None,
+            owner => Some(PlayerId(owner as usize - 1)),
+        }
+    }
+
+    pub fn ship(&self, ship_id: ShipId) -> Option<&SimShip> {
+        self.ships
+            .binary_search_by_key(&ship_id.0, |ship| ship.id.0)
+            .ok()
+            .map(|i| &self.ships[i])
+    }
+
+    pub fn depth(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let undo = match self.history.pop() {
+            Some(undo) => undo,
+            None => return false,
+        };
+
+        for (index, halite) in undo.halite.into_iter().rev() {
+            self.halite[index] = halite;
+        }
+        for (index, owner) in undo.structures.into_iter().rev() {
+            self.structures[index] = owner;
+        }
+        self.ships = undo.ships;
+        self.banks = undo.banks;
+        self.next_ship_id = undo.next_ship_id;
+        self.turn_number -= 1;
+        true
+    }
+
+    // Resolves one turn. Invalid commands are ignored.
+    pub fn step(&mut self, commands: &[Vec<Command>]) {
+        let mut undo = Undo {
+            halite: Vec::new(),
+            structures: Vec::new(),
+            ships: self.ships.clone(),
+            banks: self.banks.clone(),
+            next_ship_id: self.next_ship_id,
+        };
+
+        let inspired = self.inspired();
+        let mut directions: Vec<Option<Direction>> = vec![None; self.ships.len()];
+        let mut constructing: Vec<bool> = vec![false; self.ships.len()];
+        let mut spawning: Vec<bool> = vec![false; self.banks.len()];
+
+        for (player_index, player_commands) in commands.iter().enumerate().take(self.banks.len()) {
+            for command in player_commands {
+                let (ship_id, direction) = match *command {
+                    Command::Spawn => {
+                        spawning[player_index] = true;
+                        continue;
+                    }
+                    Command::Construct(ship_id) => (ship_id, None),
+                    Command::Move(ship_id, direction) => (ship_id, Some(direction)),
+                };
+
+                let i = match self.ships.binary_search_by_key(&ship_id.0, |ship| ship.id.0) {
+                    Ok(i) => i,
+                    Err(_) => continue,
+                };
+                if self.ships[i].owner.0 != player_index || directions[i].is_some() || constructing[i] {
+                    continue;
+                }
+                match direction {
+                    Some(direction) => directions[i] = Some(direction),
+                    None => constructing[i] = true,
+                }
+            }
+        }
+
+        let mut removed: Vec<bool> = vec![false; self.ships.len()];
+        for i in 0..self.ships.len() {
+            if constructing[i] {
+                removed[i] = self.construct(i, &mut undo);
+            }
+        }
+
+        for (player_index, spawn) in spawning.iter().enumerate() {
+            if *spawn && self.banks[player_index] >= self.rules.ship_cost {
+                self.banks[player_index] -= self.rules.ship_cost;
+                self.ships.push(SimShip {
+                    id: ShipId(self.next_ship_id),
+                    owner: PlayerId(player_index),
+                    position: self.shipyards[player_index],
+                    halite: 0,
+                });
+                self.next_ship_id += 1;
+                directions.push(None);
+                removed.push(false);
+            }
+        }
+
+        // Ships spawned this turn neither move nor mine.
+        let mut still: Vec<bool> = (0..self.ships.len()).map(|i| i < constructing.len()).collect();
+        for i in 0..self.ships.len() {
+            let direction = match directions[i] {
+                Some(direction) if direction != Direction::Still && !removed[i] => direction,
+                _ => continue,
+            };
+
+            let ratio = if inspired.get(i).cloned().unwrap_or(false) {
+                self.rules.inspired_move_cost_ratio
+            } else {
+                self.rules.move_cost_ratio
+            };
+            let cost = Engine::move_cost(self.halite_at(&self.ships[i].position), ratio);
+            if self.ships[i].halite >= cost {
+                let position = self.normalize(&self.ships[i].position.directional_offset(direction));
+                self.ships[i].halite -= cost;
+                self.ships[i].position = position;
+                still[i] = false;
+            }
+        }
+
+        let mut occupied: Vec<(usize, usize)> = (0..self.ships.len())
+            .filter(|i| !removed[*i])
+            .map(|i| (self.index(&self.ships[i].position), i))
+            .collect();
+        occupied.sort();
+        let mut start = 0;
+        while start < occupied.len() {
+            let mut end = start + 1;
+            while end < occupied.len() && occupied[end].0 == occupied[start].0 {
+                end += 1;
+            }
+            if end - start > 1 {
+                let cell = occupied[start].0;
+                let mut wreck = 0;
+                for (_, i) in &occupied[start..end] {
+                    wreck += self.ships[*i].halite;
+                    removed[*i] = true;
+                }
+                match self.structures[cell] {
+                    0 => {
+                        undo.halite.push((cell, self.halite[cell]));
+                        self.halite[cell] += wreck as u32;
+                    }
+                    owner => self.banks[owner as usize - 1] += wreck,
+                }
+            }
+            start = end;
+        }
+
+        if self.rules.capture_enabled {
+            self.capture(&removed);
+        }
+
+        for i in 0..self.ships.len() {
+            if removed[i] {
+                continue;
+            }
+            let cell = self.index(&self.ships[i].position);
+
+            if still[i] {
+                let is_inspired = inspired.get(i).cloned().unwrap_or(false);
+                let ratio = if is_inspired { self.rules.inspired_extract_ratio } else { self.rules.extract_ratio };
+                let capacity = self.rules.max_halite.saturating_sub(self.ships[i].halite);
+                let extracted = min(Engine::extraction(self.halite[cell] as usize, ratio), capacity);
+                if extracted > 0 {
+                    undo.halite.push((cell, self.halite[cell]));
+                    self.halite[cell] -= extracted as u32;
+                    self.ships[i].halite += extracted;
+                    if is_inspired {
+                        let bonus = (extracted as f64 * self.rules.inspired_bonus_multiplier) as usize;
+                        self.ships[i].halite += min(bonus, capacity - extracted);
+                    }
+                }
+            }
+
+            let owner = self.ships[i].owner;
+            if self.structures[cell] as usize == owner.0 + 1 {
+                self.banks[owner.0] += self.ships[i].halite;
+                self.ships[i].halite = 0;
+            }
+        }
+
+        let mut index = 0;
+        self.ships.retain(|_| {
+            index += 1;
+            !removed[index - 1]
+        });
+
+        self.turn_number += 1;
+        self.history.push(undo);
+    }
+
+    fn construct(&mut self, i: usize, undo: &mut Undo) -> bool {
+        let ship = self.ships[i];
+        let cell = self.index(&ship.position);
+        if self.structures[cell] != 0 {
+            return false;
+        }
+
+        let available = self.banks[ship.owner.0] + ship.halite + self.halite[cell] as usize;
+        if available < self.rules.dropoff_cost {
+            return false;
+        }
+
+        self.banks[ship.owner.0] = available - self.rules.dropoff_cost;
+        undo.halite.push((cell, self.halite[cell]));
+        undo.structures.push((cell, self.structures[cell]));
+        self.halite[cell] = 0;
+        self.structures[cell] = ship.owner.0 as u8 + 1;
+        true
+    }
+
+    fn capture(&mut self, removed: &[bool]) {
+        let mut captures: Vec<(usize, PlayerId)> = Vec::new();
+        for i in 0..self.ships.len() {
+            if removed[i] {
+                continue;
+            }
+            let mut counts = vec![0; self.banks.len()];
+            for j in 0..self.ships.len() {
+                if !removed[j] && self.distance(&self.ships[i].position, &self.ships[j].position) <= self.rules.capture_radius {
+                    counts[self.ships[j].owner.0] += 1;
+                }
+            }
+            if let Some(capturer) = Engine::capturer(&counts, self.ships[i].owner, self.rules.ships_above_for_capture) {
+                captures.push((i, capturer));
+            }
+        }
+
+        for (i, capturer) in captures {
+            self.ships[i].owner = capturer;
+        }
+    }
+
+    fn inspired(&self) -> Vec<bool> {
+        if !self.rules.inspiration_enabled {
+            return Vec::new();
+        }
+
+        self.ships
+            .iter()
+            .map(|ship| {
+                let nearby_enemies = self
+                    .ships
+                    .iter()
+                    .filter(|other| other.owner != ship.owner && self.distance(&ship.position, &other.position) <= self.rules.inspiration_radius)
+                    .count();
+                nearby_enemies >= self.rules.inspiration_ship_count
+            })
+            .collect()
+    }
+
+    fn distance(&self, a: &Position, b: &Position) -> usize {
+        let dx = (a.x - b.x).unsigned_abs() as usize;
+        let dy = (a.y - b.y).unsigned_abs() as usize;
+        min(dx, self.width - dx) + min(dy, self.height - dy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::arena::Arena;
+    use hlt::baseline::GreedyMiner;
+    use hlt::baseline::Rammer;
+    use hlt::baseline::RandomMover;
+    use hlt::command::Command;
+    use hlt::forward_model::ForwardModel;
+    use hlt::game::Game;
+    use hlt::strategy::Strategy;
+    use hlt::PlayerId;
+
+    type State = (Vec<(usize, usize, i32, i32, usize)>, Vec<usize>, Vec<u32>, Vec<u8>);
+
+    fn state(model: &ForwardModel) -> State {
+        let ships = model.ships.iter().map(|s| (s.id.0, s.owner.0, s.position.x, s.position.y, s.halite)).collect();
+        (ships, model.banks.clone(), model.halite.clone(), model.structures.clone())
+    }
+
+    // The engine's game in the model's terms, built the same way the model is.
+    fn engine_state(game: &Game) -> State {
+        state(&ForwardModel::from_game(game))
+    }
+
+    // Turns a ship into a dropoff now and then, which none of the baselines do on their own.
+    fn with_construct(game: &Game, player_id: PlayerId, mut commands: Vec<Command>) -> Vec<Command> {
+        if !game.turn_number.is_multiple_of(25) {
+            return commands;
+        }
+        let ship_id = match game.players[player_id.0].ship_ids.first() {
+            Some(ship_id) => *ship_id,
+            None => return commands,
+        };
+        commands.retain(|command| match command {
+            Command::Move(id, _) | Command::Construct(id) => *id != ship_id,
+            Command::Spawn => true,
+        });
+        commands.push(Command::Construct(ship_id));
+        commands
+    }
+
+    fn check_against_engine(seed: u64, num_players: usize, capture: bool) {
+        let mut arena = Arena::generate(seed, 24, 24, num_players).unwrap();
+        arena.game.constants.capture_enabled = capture;
+        let mut strategies: Vec<Box<dyn Strategy>> = (0..num_players)
+            .map(|i| -> Box<dyn Strategy> {
+                match i % 3 {
+                    0 => Box::new(GreedyMiner::new(&arena.game)),
+                    1 => Box::new(Rammer::new(&arena.game)),
+                    _ => Box::new(RandomMover::new(&arena.game, seed + i as u64)),
+                }
+            })
+            .collect();
+
+        let mut model = ForwardModel::from_game(&arena.game);
+        let start = state(&model);
+        let mut turns = 0;
+        while !arena.is_over() {
+            let commands: Vec<Vec<Command>> = strategies
+                .iter_mut()
+                .enumerate()
+                .map(|(i, strategy)| {
+                    let view = arena.view(PlayerId(i));
+                    with_construct(&view, PlayerId(i), strategy.play_turn(&view))
+                })
+                .collect();
+            model.step(&commands);
+            arena.resolve(&commands);
+            turns += 1;
+
+            assert_eq!(model.turn_number, arena.game.turn_number);
+            assert!(state(&model) == engine_state(&arena.game), "differs from the engine after turn {}", model.turn_number - 1);
+        }
+
+        assert_eq!(model.depth(), turns);
+        while model.undo() {}
+        assert!(state(&model) == start, "undo did not restore the starting state");
+    }
+
+    #[test]
+    fn steps_like_the_engine_two_players() {
+        check_against_engine(11, 2, false);
+    }
+
+    #[test]
+    fn steps_like_the_engine_four_players_with_capture() {
+        check_against_engine(12, 4, true);
+    }
+}
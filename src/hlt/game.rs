@@ -6,11 +6,18 @@ use hlt::input::Input;
 use hlt::log::Log;
 use hlt::map_cell::Structure;
 use hlt::player::Player;
+use hlt::prediction::Prediction;
+use hlt::protocol_error::ProtocolError;
 use hlt::ship::Ship;
+use hlt::transcript::TranscriptRecorder;
 use hlt::DropoffId;
 use hlt::PlayerId;
 use hlt::ShipId;
 use std::collections::HashMap;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
 
 pub struct Game {
     pub constants: Constants,
@@ -21,27 +28,57 @@ pub struct Game {
     pub dropoffs: HashMap<DropoffId, Dropoff>,
     pub map: GameMap,
     input: Input,
+    output: Box<dyn Write>,
+    prediction: Option<Prediction>,
 }
 
 impl Game {
-    pub fn new() -> Game {
-        let mut input = Input::new();
-        let constants = Constants::new(&input.read_and_return_line());
+    // Talks to the server over stdin and stdout.
+    pub fn new() -> Result<Game, ProtocolError> {
+        let game = Game::from_io(Box::new(BufReader::new(io::stdin())), Box::new(io::stdout()))?;
+        Log::open(game.my_id.0);
+        Ok(game)
+    }
 
-        input.read_and_parse_line();
-        let num_players = input.next_usize();
-        let my_id = PlayerId(input.next_usize());
+    // Like new, but with every line read and written kept in bot-N.transcript.
+    pub fn new_recorded(recorder: &TranscriptRecorder) -> Result<Game, ProtocolError> {
+        let reader = recorder.reader(Box::new(BufReader::new(io::stdin())));
+        let game = Game::from_io(reader, recorder.writer(Box::new(io::stdout())))?;
+        Log::open(game.my_id.0);
+        if recorder.open(&format!("bot-{}.transcript", game.my_id.0)).is_err() {
+            Log::log("could not open the transcript");
+        }
+        Ok(game)
+    }
 
-        Log::open(my_id.0);
+    // Reads the initial state from `reader`; frames are then read from it by
+    // update_frame, and ready and end_turn write to `output`.
+    pub fn from_io(reader: Box<dyn BufRead>, output: Box<dyn Write>) -> Result<Game, ProtocolError> {
+        let mut input = Input::new(reader);
+        let constants = Constants::new(&input.read_and_return_line()?)?;
+
+        input.read_and_parse_line()?;
+        let num_players = input.next_usize()?;
+        let my_id = PlayerId(input.next_usize()?);
+        if my_id.0 >= num_players {
+            return Err(ProtocolError::OutOfRange { what: "player id", value: my_id.0 as i64 });
+        }
 
         let mut players: Vec<Player> = Vec::new();
-        for _ in 0..num_players {
-            players.push(Player::generate(&mut input));
+        for i in 0..num_players {
+            let player = Player::generate(&mut input)?;
+            if player.id.0 != i {
+                return Err(ProtocolError::OutOfRange { what: "player id", value: player.id.0 as i64 });
+            }
+            players.push(player);
         }
 
-        let map = GameMap::generate(&mut input);
+        let map = GameMap::generate(&mut input)?;
+        for player in &players {
+            map.check_position(&player.shipyard.position)?;
+        }
 
-        Game {
+        Ok(Game {
             constants,
             turn_number: 0,
             my_id,
@@ -50,18 +87,55 @@ impl Game {
             dropoffs: HashMap::new(),
             map,
             input,
+            output,
+            prediction: None,
+        })
+    }
+
+    pub fn construct(constants: Constants, my_id: PlayerId, players: Vec<Player>, mut map: GameMap) -> Game {
+        for player in &players {
+            map.at_entity_mut(&player.shipyard).structure = Structure::Shipyard(player.id);
+        }
+
+        Game {
+            constants,
+            turn_number: 0,
+            my_id,
+            players,
+            ships: HashMap::new(),
+            dropoffs: HashMap::new(),
+            map,
+            input: Input::empty(),
+            output: Box::new(io::sink()),
+            prediction: None,
         }
     }
 
-    pub fn ready(name: &str) {
-        println!("{}", name);
+    // Copy of the game as seen by the given player, for driving bots without the protocol.
+    pub fn view_for(&self, player_id: PlayerId) -> Game {
+        Game {
+            constants: self.constants.clone(),
+            turn_number: self.turn_number,
+            my_id: player_id,
+            players: self.players.clone(),
+            ships: self.ships.clone(),
+            dropoffs: self.dropoffs.clone(),
+            map: self.map.clone(),
+            input: Input::empty(),
+            output: Box::new(io::sink()),
+            prediction: None,
+        }
     }
 
-    pub fn update_frame(&mut self) {
+    pub fn ready(&mut self, name: &str) {
+        self.send_line(name);
+    }
+
+    pub fn update_frame(&mut self) -> Result<(), ProtocolError> {
         let input = &mut self.input;
 
-        input.read_and_parse_line();
-        self.turn_number = input.next_usize();
+        input.read_and_parse_line()?;
+        self.turn_number = input.next_usize()?;
 
         Log::log(&format!(
             "=============== TURN {} ================",
@@ -72,11 +146,14 @@ impl Game {
         self.dropoffs.clear();
 
         for _ in 0..self.players.len() {
-            input.read_and_parse_line();
-            let current_player_id = input.next_usize();
-            let num_ships = input.next_usize();
-            let num_dropoffs = input.next_usize();
-            let halite = input.next_usize();
+            input.read_and_parse_line()?;
+            let current_player_id = input.next_usize()?;
+            let num_ships = input.next_usize()?;
+            let num_dropoffs = input.next_usize()?;
+            let halite = input.next_usize()?;
+            if current_player_id >= self.players.len() {
+                return Err(ProtocolError::OutOfRange { what: "player id", value: current_player_id as i64 });
+            }
 
             self.players[current_player_id].update(
                 input,
@@ -86,10 +163,24 @@ impl Game {
                 num_ships,
                 num_dropoffs,
                 halite,
-            );
+            )?;
         }
 
-        self.map.update(input);
+        self.map.update(input)?;
+        for ship in self.ships.values() {
+            self.map.check_position(&ship.position)?;
+        }
+        for dropoff in self.dropoffs.values() {
+            self.map.check_position(&dropoff.position)?;
+        }
+
+        // The server only says a new dropoff's cell is now empty, which map.update took
+        // for mining. Dropoffs are new until their structure is set below.
+        for dropoff in self.dropoffs.values() {
+            if self.map.at_entity(dropoff).structure != Structure::Dropoff(dropoff.id) {
+                self.map.reclassify_as_cleared(&dropoff.position);
+            }
+        }
 
         for player in &self.players {
             let shipyard = &player.shipyard;
@@ -100,13 +191,31 @@ impl Game {
                 self.map.at_entity_mut(dropoff).structure = Structure::Dropoff(*dropoff_id);
             }
         }
+
+        if let Some(prediction) = self.prediction.take() {
+            for mismatch in prediction.mismatches(self) {
+                Log::log(&format!("prediction: {}", mismatch));
+            }
+        }
+        Ok(())
+    }
+
+    // Remembers what the next frame should look like after our commands, so that
+    // update_frame can log where the real one differs.
+    pub fn expect_next_frame(&mut self, commands: &[Command]) {
+        self.prediction = Some(Prediction::new(self, commands));
     }
 
-    pub fn end_turn(commands: &[Command]) {
-        for command in commands {
-            print!("{} ", command.0);
+    pub fn end_turn(&mut self, commands: &[Command]) {
+        let line: Vec<String> = commands.iter().map(|command| command.to_string()).collect();
+        self.send_line(&line.join(" "));
+    }
+
+    fn send_line(&mut self, line: &str) {
+        let sent = writeln!(self.output, "{}", line).and_then(|_| self.output.flush());
+        if sent.is_err() {
+            Log::log("Output connection to server closed.");
         }
-        println!();
     }
 
     pub fn enemy_players(&self) -> Vec<&Player> {
@@ -119,3 +228,190 @@ impl Game {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use hlt::game::Game;
+    use hlt::map_cell::Structure;
+    use hlt::position::Position;
+    use hlt::protocol_error::ProtocolError;
+    use hlt::transcript::CapturedOutput;
+    use hlt::DropoffId;
+    use hlt::PlayerId;
+    use hlt::ShipId;
+    use std::io::Cursor;
+
+    const INIT: &str = r#"{"MAX_ENERGY":1000,"MAX_TURNS":10,"NEW_ENTITY_ENERGY_COST":1000}
+2 1
+0 1 2
+1 6 2
+8 4
+10 20 30 40 50 60 70 80
+11 21 31 41 51 61 71 81
+12 0 32 42 52 62 0 82
+13 23 33 43 53 63 73 83
+"#;
+
+    const TURN_1: &str = "1
+0 1 0 4000
+0 1 2 0
+1 0 0 5000
+1
+1 2 17
+";
+
+    const TURN_2: &str = "2
+0 1 1 500
+0 2 2 30
+1 4 3
+1 1 0 3900
+2 6 3 10
+2
+2 2 0
+6 3 25
+";
+
+    fn game(input: &str) -> (Game, CapturedOutput) {
+        let output = CapturedOutput::new();
+        let reader = Box::new(Cursor::new(input.to_string().into_bytes()));
+        let game = Game::from_io(reader, output.writer()).unwrap();
+        (game, output)
+    }
+
+    fn try_game(input: &str) -> Result<Game, ProtocolError> {
+        let reader = Box::new(Cursor::new(input.to_string().into_bytes()));
+        Game::from_io(reader, CapturedOutput::new().writer())
+    }
+
+    // The error from reading `frame` after INIT.
+    fn frame_error(frame: &str) -> ProtocolError {
+        let mut game = try_game(&format!("{}{}", INIT, frame)).unwrap();
+        game.update_frame().unwrap_err()
+    }
+
+    #[test]
+    fn reads_the_initial_state() {
+        let (game, _) = game(INIT);
+        assert!(game.my_id == PlayerId(1));
+        assert_eq!(game.players.len(), 2);
+        assert!(game.players[1].shipyard.position == Position { x: 6, y: 2 });
+        assert_eq!((game.map.width, game.map.height), (8, 4));
+        assert_eq!(game.map.at_position(&Position { x: 3, y: 1 }).halite, 41);
+        assert_eq!(game.map.initial_halite, game.map.current_halite);
+        assert_eq!(game.constants.max_turns, 10);
+    }
+
+    #[test]
+    fn reads_two_turns() {
+        let (mut game, _) = game(&format!("{}{}{}", INIT, TURN_1, TURN_2));
+
+        game.update_frame().unwrap();
+        assert_eq!(game.turn_number, 1);
+        assert_eq!(game.players[0].halite, 4000);
+        assert_eq!(game.players[1].halite, 5000);
+        assert_eq!(game.ships.len(), 1);
+        assert!(game.ships[&ShipId(0)].position == Position { x: 1, y: 2 });
+        assert_eq!(game.map.at_position(&Position { x: 1, y: 2 }).halite, 17);
+        assert!(game.map.at_position(&Position { x: 1, y: 2 }).structure == Structure::Shipyard(PlayerId(0)));
+
+        let before = game.map.current_halite;
+        game.update_frame().unwrap();
+        assert_eq!(game.turn_number, 2);
+        assert_eq!(game.players[0].halite, 500);
+        assert_eq!(game.players[1].halite, 3900);
+        assert!(game.players[0].ship_ids == vec![ShipId(0)]);
+        assert!(game.players[1].ship_ids == vec![ShipId(2)]);
+        assert!(game.ships[&ShipId(0)].position == Position { x: 2, y: 2 });
+        assert_eq!(game.ships[&ShipId(0)].halite, 30);
+        assert!(game.ships[&ShipId(2)].owner == PlayerId(1));
+        assert!(game.players[0].dropoff_ids == vec![DropoffId(1)]);
+        assert!(game.dropoffs[&DropoffId(1)].position == Position { x: 4, y: 3 });
+        assert!(game.map.at_position(&Position { x: 4, y: 3 }).structure == Structure::Dropoff(DropoffId(1)));
+        assert_eq!(game.map.at_position(&Position { x: 6, y: 3 }).halite, 25);
+        assert_eq!(game.map.changes.len(), 2);
+        assert_eq!(game.map.current_halite, before - 32 - 48);
+    }
+
+    #[test]
+    fn a_new_dropoffs_cell_is_cleared_not_mined() {
+        let turn_2 = "2
+0 1 1 500
+0 2 2 30
+1 4 3
+1 0 0 5000
+2
+4 3 0
+2 2 20
+";
+        let (mut game, _) = game(&format!("{}{}{}", INIT, TURN_1, turn_2));
+        game.update_frame().unwrap();
+        let before = game.map.current_halite;
+
+        game.update_frame().unwrap();
+        assert_eq!(game.map.mined_this_turn, 32 - 20);
+        assert_eq!(game.map.dropped_this_turn, 0);
+        assert_eq!(game.map.current_halite, before - 53 - 12);
+        assert_eq!(game.map.changes.len(), 2);
+    }
+
+    #[test]
+    fn writes_the_name_and_commands() {
+        let (mut game, output) = game(INIT);
+        game.ready("tester");
+        game.end_turn(&[game.players[1].shipyard.spawn()]);
+        assert_eq!(output.lines(), vec!["tester".to_string(), "g".to_string()]);
+    }
+
+    #[test]
+    fn empty_input_is_closed() {
+        assert_eq!(try_game("").err(), Some(ProtocolError::Closed));
+        assert_eq!(frame_error(""), ProtocolError::Closed);
+    }
+
+    #[test]
+    fn input_ending_mid_init_is_closed() {
+        let truncated: String = INIT.lines().take(4).map(|line| format!("{}\n", line)).collect();
+        assert_eq!(try_game(&truncated).err(), Some(ProtocolError::Closed));
+    }
+
+    #[test]
+    fn short_line_is_a_missing_token() {
+        let error = frame_error("1\n0 1 0\n");
+        assert_eq!(error, ProtocolError::MissingToken { line: "0 1 0".to_string() });
+    }
+
+    #[test]
+    fn garbled_number_is_a_bad_token() {
+        let error = try_game(&INIT.replace("\n8 4\n", "\n8 four\n")).err();
+        assert_eq!(error, Some(ProtocolError::BadToken { token: "four".to_string(), expected: "usize" }));
+    }
+
+    #[test]
+    fn ship_off_the_map_is_out_of_range() {
+        let error = frame_error(&TURN_1.replace("0 1 2 0", "0 9 2 0"));
+        assert_eq!(error, ProtocolError::OutOfRange { what: "x", value: 9 });
+    }
+
+    #[test]
+    fn unknown_player_is_out_of_range() {
+        let error = try_game(&INIT.replace("\n2 1\n", "\n2 2\n")).err();
+        assert_eq!(error, Some(ProtocolError::OutOfRange { what: "player id", value: 2 }));
+    }
+
+    #[test]
+    fn constants_that_are_not_json_are_bad_constants() {
+        match try_game(&INIT.replacen("{", "[", 1)) {
+            Err(ProtocolError::BadConstants(_)) => (),
+            other => panic!("expected BadConstants, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn constant_of_the_wrong_type_is_a_bad_constant() {
+        let error = try_game(&INIT.replace("\"MAX_TURNS\":10", "\"MAX_TURNS\":\"ten\"")).err();
+        match error {
+            Some(ProtocolError::BadConstant { key, .. }) => assert_eq!(key, "MAX_TURNS"),
+            other => panic!("expected BadConstant, got {:?}", other),
+        }
+    }
+}
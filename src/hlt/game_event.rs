@@ -0,0 +1,415 @@
+use hlt::command::Command;
+use hlt::direction::Direction;
+use hlt::engine::Engine;
+use hlt::game::Game;
+use hlt::map_cell::Structure;
+use hlt::position::Position;
+use hlt::DropoffId;
+use hlt::PlayerId;
+use hlt::ShipId;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+
+#[derive(Clone, PartialEq)]
+pub enum GameEvent {
+    ShipSpawned { ship_id: ShipId, owner: PlayerId, position: Position },
+    ShipMoved { ship_id: ShipId, owner: PlayerId, from: Position, to: Position, direction: Direction },
+    // The ship stayed put with less cargo than it costs to leave its cell.
+    ShipStalled { ship_id: ShipId, owner: PlayerId, position: Position, halite: usize, move_cost: usize },
+    // `halite_dropped` is the cargo the ships had left after paying for their moves. It
+    // lands on the cell, or in the bank of the structure's owner.
+    ShipsCollided { position: Position, ship_ids: Vec<ShipId>, halite_dropped: usize },
+    HaliteDeposited { ship_id: ShipId, owner: PlayerId, position: Position, structure: Structure, halite: usize },
+    DropoffConstructed { dropoff_id: DropoffId, owner: PlayerId, position: Position, ship_id: Option<ShipId> },
+}
+
+impl GameEvent {
+    // Everything that happened between two consecutive frames. Only positions, cargo and
+    // structures are visible, so collisions are placed on the cell that the most of the
+    // vanished ships could have reached, preferring cells whose halite went up, or for a
+    // structure, whose owner's bank went up by more than the deposits account for. A ship
+    // that vanishes alone ran into one built that turn, which never shows up in a frame:
+    // its collision goes on a shipyard, and that spawn is not reported.
+    pub fn derive(previous: &Game, next: &Game) -> Vec<GameEvent> {
+        let mut events: Vec<GameEvent> = Vec::new();
+        let inspired = Engine::inspired_ships(previous);
+        let move_cost = |ship_id: &ShipId| Engine::ship_move_cost(previous, &inspired, &previous.ships[ship_id]);
+
+        let mut new_ship_ids: Vec<&ShipId> = next.ships.keys().filter(|id| !previous.ships.contains_key(id)).collect();
+        new_ship_ids.sort_by_key(|id| id.0);
+        for ship_id in new_ship_ids {
+            let ship = &next.ships[ship_id];
+            events.push(GameEvent::ShipSpawned { ship_id: *ship_id, owner: ship.owner, position: ship.position });
+        }
+
+        let mut constructed_from: HashSet<ShipId> = HashSet::new();
+        let mut new_dropoff_ids: Vec<&DropoffId> =
+            next.dropoffs.keys().filter(|id| !previous.dropoffs.contains_key(id)).collect();
+        new_dropoff_ids.sort_by_key(|id| id.0);
+        for dropoff_id in new_dropoff_ids {
+            let dropoff = &next.dropoffs[dropoff_id];
+            let ship_id = previous
+                .ships
+                .values()
+                .find(|ship| {
+                    ship.owner == dropoff.owner && ship.position == dropoff.position && !next.ships.contains_key(&ship.id)
+                })
+                .map(|ship| ship.id);
+            if let Some(ship_id) = ship_id {
+                constructed_from.insert(ship_id);
+            }
+            events.push(GameEvent::DropoffConstructed {
+                dropoff_id: *dropoff_id,
+                owner: dropoff.owner,
+                position: dropoff.position,
+                ship_id,
+            });
+        }
+
+        let mut ship_ids: Vec<&ShipId> = previous.ships.keys().collect();
+        ship_ids.sort_by_key(|id| id.0);
+        let mut vanished: Vec<ShipId> = Vec::new();
+        for ship_id in ship_ids {
+            let before = &previous.ships[ship_id];
+            let after = match next.ships.get(ship_id) {
+                Some(after) => after,
+                None => {
+                    if !constructed_from.contains(ship_id) {
+                        vanished.push(*ship_id);
+                    }
+                    continue;
+                }
+            };
+
+            let cost = move_cost(ship_id);
+            if before.position != after.position {
+                let direction = Direction::get_all_cardinals()
+                    .into_iter()
+                    .find(|d| next.map.normalize(&before.position.directional_offset(*d)) == after.position)
+                    .unwrap_or(Direction::Still);
+                events.push(GameEvent::ShipMoved {
+                    ship_id: *ship_id,
+                    owner: before.owner,
+                    from: before.position,
+                    to: after.position,
+                    direction,
+                });
+            } else if before.halite < cost {
+                events.push(GameEvent::ShipStalled {
+                    ship_id: *ship_id,
+                    owner: before.owner,
+                    position: before.position,
+                    halite: before.halite,
+                    move_cost: cost,
+                });
+            }
+
+            if after.halite == 0 && Engine::structure_owner(next, &after.position) == Some(after.owner) {
+                let halite = if before.position != after.position {
+                    before.halite.saturating_sub(cost)
+                } else {
+                    before.halite
+                };
+                if halite > 0 {
+                    events.push(GameEvent::HaliteDeposited {
+                        ship_id: *ship_id,
+                        owner: after.owner,
+                        position: after.position,
+                        structure: next.map.at_position(&after.position).structure,
+                        halite,
+                    });
+                }
+            }
+        }
+
+        let collisions = GameEvent::collisions(previous, next, &inspired, &events, vanished);
+        events.extend(collisions);
+        events
+    }
+
+    fn collisions(
+        previous: &Game,
+        next: &Game,
+        inspired: &HashSet<ShipId>,
+        other_events: &[GameEvent],
+        mut vanished: Vec<ShipId>,
+    ) -> Vec<GameEvent> {
+        let mut events: Vec<GameEvent> = Vec::new();
+        let reachable = |ship_id: &ShipId| -> Vec<Position> {
+            let position = previous.ships[ship_id].position;
+            position.get_surrounding_cardinals().iter().map(|x| next.map.normalize(x)).collect()
+        };
+
+        // What reached each bank besides deposits, spawns and constructions.
+        let mut bank_gain: Vec<isize> = next
+            .players
+            .iter()
+            .zip(&previous.players)
+            .map(|(after, before)| after.halite as isize - before.halite as isize)
+            .collect();
+        for event in other_events {
+            match event {
+                GameEvent::HaliteDeposited { owner, halite, .. } => bank_gain[owner.0] -= *halite as isize,
+                GameEvent::ShipSpawned { owner, .. } => bank_gain[owner.0] += previous.constants.ship_cost as isize,
+                GameEvent::DropoffConstructed { owner, position, ship_id, .. } => {
+                    let cargo = ship_id.map_or(0, |id| previous.ships[&id].halite);
+                    let cell = previous.map.at_position(position).halite;
+                    bank_gain[owner.0] += previous.constants.dropoff_cost as isize - (cargo + cell) as isize;
+                }
+                _ => (),
+            }
+        }
+        let halite_gain = |position: &Position| match Engine::structure_owner(next, position) {
+            Some(owner) => bank_gain[owner.0],
+            None => next.map.at_position(position).halite as isize - previous.map.at_position(position).halite as isize,
+        };
+        let is_shipyard =
+            |position: &Position| matches!(next.map.at_position(position).structure, Structure::Shipyard(_));
+
+        while !vanished.is_empty() {
+            let mut candidates: HashMap<Position, Vec<ShipId>> = HashMap::new();
+            for ship_id in &vanished {
+                for position in reachable(ship_id) {
+                    candidates.entry(position).or_default().push(*ship_id);
+                }
+            }
+
+            let (position, ship_ids) = candidates
+                .into_iter()
+                .max_by_key(|(position, ship_ids)| {
+                    let lone_on_shipyard = ship_ids.len() == 1 && is_shipyard(position);
+                    (ship_ids.len(), lone_on_shipyard, halite_gain(position), -position.y, -position.x)
+                })
+                .unwrap();
+
+            let halite_dropped = ship_ids
+                .iter()
+                .map(|ship_id| {
+                    let ship = &previous.ships[ship_id];
+                    if ship.position == position {
+                        ship.halite
+                    } else {
+                        ship.halite.saturating_sub(Engine::ship_move_cost(previous, inspired, ship))
+                    }
+                })
+                .sum();
+
+            vanished.retain(|x| !ship_ids.contains(x));
+            events.push(GameEvent::ShipsCollided { position, ship_ids, halite_dropped });
+        }
+        events
+    }
+}
+
+fn write_position(f: &mut fmt::Formatter, position: &Position) -> fmt::Result {
+    write!(f, "({}, {})", position.x, position.y)
+}
+
+impl fmt::Display for GameEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameEvent::ShipSpawned { ship_id, owner, position } => {
+                write!(f, "spawned ship {} of player {} at ", ship_id.0, owner.0)?;
+                write_position(f, position)
+            }
+            GameEvent::ShipMoved { ship_id, owner, from, to, direction } => {
+                write!(f, "moved ship {} of player {} {} from ", ship_id.0, owner.0, direction.get_char_encoding())?;
+                write_position(f, from)?;
+                write!(f, " to ")?;
+                write_position(f, to)
+            }
+            GameEvent::ShipStalled { ship_id, owner, position, halite, move_cost } => {
+                write!(f, "stalled ship {} of player {} at ", ship_id.0, owner.0)?;
+                write_position(f, position)?;
+                write!(f, " with {} of {} needed", halite, move_cost)
+            }
+            GameEvent::ShipsCollided { position, ship_ids, halite_dropped } => {
+                let ids: Vec<String> = ship_ids.iter().map(|id| id.0.to_string()).collect();
+                write!(f, "collided ships {} at ", ids.join(", "))?;
+                write_position(f, position)?;
+                write!(f, " dropping {}", halite_dropped)
+            }
+            GameEvent::HaliteDeposited { ship_id, owner, position, structure, halite } => {
+                let structure = match structure {
+                    Structure::Dropoff(dropoff_id) => format!("dropoff {}", dropoff_id.0),
+                    _ => "shipyard".to_string(),
+                };
+                write!(f, "deposited {} from ship {} of player {} at {} ", halite, ship_id.0, owner.0, structure)?;
+                write_position(f, position)
+            }
+            GameEvent::DropoffConstructed { dropoff_id, owner, position, ship_id } => {
+                write!(f, "constructed dropoff {} of player {} at ", dropoff_id.0, owner.0)?;
+                write_position(f, position)?;
+                match ship_id {
+                    Some(ship_id) => write!(f, " from ship {}", ship_id.0),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+// One line per event, prefixed with the turn it happened on, in bot-<id>.events next to
+// the regular log.
+pub struct EventLog {
+    file: File,
+}
+
+impl EventLog {
+    pub fn open(player_id: PlayerId) -> io::Result<EventLog> {
+        let file = File::create(format!("bot-{}.events", player_id.0))?;
+        Ok(EventLog { file })
+    }
+
+    pub fn write(&mut self, turn_number: usize, events: &[GameEvent]) -> io::Result<()> {
+        for event in events {
+            writeln!(self.file, "{} {}", turn_number, event)?;
+        }
+        Ok(())
+    }
+
+    // The commands a player most likely sent on that turn, as they would go over the wire.
+    pub fn write_commands(&mut self, turn_number: usize, player_id: PlayerId, commands: &[Command]) -> io::Result<()> {
+        let commands: Vec<String> = commands.iter().map(|command| command.to_string()).collect();
+        writeln!(self.file, "{} commands of player {}: {}", turn_number, player_id.0, commands.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::command::Command;
+    use hlt::direction::Direction;
+    use hlt::game_event::GameEvent;
+    use hlt::map_cell::Structure;
+    use hlt::test_game::*;
+    use hlt::DropoffId;
+    use hlt::PlayerId;
+    use hlt::ShipId;
+
+    const YARDS: [(i32, i32); 2] = [(2, 2), (13, 13)];
+
+    fn describe(events: &[GameEvent]) -> Vec<String> {
+        events.iter().map(|event| event.to_string()).collect()
+    }
+
+    #[test]
+    fn a_move() {
+        let mut game = game(16, &YARDS);
+        add_ship(&mut game, 0, 0, 5, 5, 50);
+        let (previous, _) = step(&mut game, &[vec![Command::Move(ShipId(0), Direction::East)], vec![]]);
+
+        let events = GameEvent::derive(&previous, &game);
+        assert!(
+            events
+                == vec![GameEvent::ShipMoved {
+                    ship_id: ShipId(0),
+                    owner: PlayerId(0),
+                    from: pos(5, 5),
+                    to: pos(6, 5),
+                    direction: Direction::East,
+                }]
+        );
+    }
+
+    #[test]
+    fn a_stall() {
+        let mut game = game(16, &YARDS);
+        add_ship(&mut game, 0, 0, 5, 5, 5);
+        let (previous, _) = step(&mut game, &[vec![Command::Move(ShipId(0), Direction::East)], vec![]]);
+
+        let events = GameEvent::derive(&previous, &game);
+        assert!(
+            events
+                == vec![GameEvent::ShipStalled {
+                    ship_id: ShipId(0),
+                    owner: PlayerId(0),
+                    position: pos(5, 5),
+                    halite: 5,
+                    move_cost: 10,
+                }]
+        );
+    }
+
+    #[test]
+    fn a_spawn() {
+        let mut game = game(16, &YARDS);
+        let (previous, _) = step(&mut game, &[vec![], vec![Command::Spawn]]);
+
+        let events = GameEvent::derive(&previous, &game);
+        assert!(events == vec![GameEvent::ShipSpawned { ship_id: ShipId(0), owner: PlayerId(1), position: pos(13, 13) }]);
+    }
+
+    #[test]
+    fn a_construction() {
+        let mut game = game(16, &YARDS);
+        add_ship(&mut game, 0, 3, 5, 5, 300);
+        let (previous, _) = step(&mut game, &[vec![Command::Construct(ShipId(3))], vec![]]);
+
+        let events = GameEvent::derive(&previous, &game);
+        assert!(
+            events
+                == vec![GameEvent::DropoffConstructed {
+                    dropoff_id: DropoffId(0),
+                    owner: PlayerId(0),
+                    position: pos(5, 5),
+                    ship_id: Some(ShipId(3)),
+                }]
+        );
+    }
+
+    #[test]
+    fn a_collision_between_two_ships() {
+        let mut game = game(16, &YARDS);
+        add_ship(&mut game, 0, 0, 3, 4, 100);
+        add_ship(&mut game, 1, 1, 4, 5, 50);
+        let commands = vec![vec![Command::Move(ShipId(0), Direction::South)], vec![Command::Move(ShipId(1), Direction::West)]];
+        let (previous, _) = step(&mut game, &commands);
+
+        let events = GameEvent::derive(&previous, &game);
+        assert_eq!(describe(&events), vec!["collided ships 0, 1 at (3, 5) dropping 130"]);
+    }
+
+    #[test]
+    fn a_collision_on_a_dropoff() {
+        let mut game = game(16, &YARDS);
+        put_halite(&mut game, 6, 6, 0);
+        add_dropoff(&mut game, 0, 0, 6, 6);
+        add_ship(&mut game, 0, 0, 6, 5, 100);
+        add_ship(&mut game, 1, 1, 7, 6, 50);
+        // A deposit at the shipyard on the same turn must not be mistaken for the wreck.
+        add_ship(&mut game, 0, 2, 2, 3, 40);
+        let commands = vec![
+            vec![Command::Move(ShipId(0), Direction::South), Command::Move(ShipId(2), Direction::North)],
+            vec![Command::Move(ShipId(1), Direction::West)],
+        ];
+        let (previous, _) = step(&mut game, &commands);
+        assert_eq!(game.players[0].halite, 5000 + 130 + 30);
+
+        let events = GameEvent::derive(&previous, &game);
+        assert_eq!(
+            describe(&events),
+            vec![
+                "moved ship 2 of player 0 n from (2, 3) to (2, 2)",
+                "deposited 30 from ship 2 of player 0 at shipyard (2, 2)",
+                "collided ships 0, 1 at (6, 6) dropping 130",
+            ]
+        );
+        assert!(game.map.at_position(&pos(6, 6)).structure == Structure::Dropoff(DropoffId(0)));
+    }
+
+    #[test]
+    fn a_ship_built_and_destroyed_on_the_same_turn() {
+        let mut game = game(16, &YARDS);
+        add_ship(&mut game, 0, 0, 2, 2, 50);
+        let (previous, result) = step(&mut game, &[vec![Command::Spawn], vec![]]);
+        assert!(result.spawned == vec![ShipId(1)]);
+
+        // The new ship is in neither frame, so only the wreck of the one it hit is seen.
+        let events = GameEvent::derive(&previous, &game);
+        assert_eq!(describe(&events), vec!["collided ships 0 at (2, 2) dropping 50"]);
+    }
+}
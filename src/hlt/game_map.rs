@@ -3,16 +3,47 @@ use hlt::input::Input;
 use hlt::map_cell::MapCell;
 use hlt::map_cell::Structure;
 use hlt::position::Position;
+use hlt::protocol_error::ProtocolError;
 use std::cmp::min;
 
+// A cell whose halite changed this turn.
+#[derive(Copy, Clone)]
+pub struct CellChange {
+    pub position: Position,
+    pub old: usize,
+    pub new: usize,
+}
+
+// Halite totals are kept up to date as cells change: `initial_halite` is what the map
+// started with, `current_halite` what is on it now. The per-turn amounts and `changes`
+// cover everything since the last `begin_turn`.
+#[derive(Clone)]
 pub struct GameMap {
     pub width: usize,
     pub height: usize,
-    pub total_halite: usize,
+    pub initial_halite: usize,
+    pub current_halite: usize,
+    pub mined_this_turn: usize,
+    pub dropped_this_turn: usize,
+    pub changes: Vec<CellChange>,
     pub cells: Vec<Vec<MapCell>>,
 }
 
 impl GameMap {
+    pub fn new(width: usize, height: usize, cells: Vec<Vec<MapCell>>) -> GameMap {
+        let total_halite = cells.iter().flatten().map(|cell| cell.halite).sum();
+        GameMap {
+            width,
+            height,
+            initial_halite: total_halite,
+            current_halite: total_halite,
+            mined_this_turn: 0,
+            dropped_this_turn: 0,
+            changes: Vec::new(),
+            cells,
+        }
+    }
+
     pub fn at_position(&self, position: &Position) -> &MapCell {
         let normalized = self.normalize(position);
         &self.cells[normalized.y as usize][normalized.x as usize]
@@ -52,34 +83,102 @@ impl GameMap {
         Position { x, y }
     }
 
-    pub fn update(&mut self, input: &mut Input) {
-        input.read_and_parse_line();
-        let update_count = input.next_usize();
+    pub fn begin_turn(&mut self) {
+        self.mined_this_turn = 0;
+        self.dropped_this_turn = 0;
+        self.changes.clear();
+    }
+
+    // Sets a cell's halite and keeps the totals and this turn's changes in step. Halite
+    // taken from a cell counts as mined, halite added to one as dropped.
+    pub fn set_halite(&mut self, position: &Position, halite: usize) {
+        let old = self.replace_halite(position, halite);
+        if halite < old {
+            self.mined_this_turn += old - halite;
+        } else {
+            self.dropped_this_turn += halite - old;
+        }
+    }
+
+    // Empties a cell that a ship turned into a dropoff. Its halite goes to the builder's
+    // bank, so it is neither mined nor dropped.
+    pub fn clear_halite(&mut self, position: &Position) {
+        self.replace_halite(position, 0);
+    }
+
+    // For a cell emptied with set_halite that turns out to have been cleared for a
+    // dropoff: takes what it lost this turn back out of the mined halite.
+    pub fn reclassify_as_cleared(&mut self, position: &Position) {
+        let position = self.normalize(position);
+        if let Some(change) = self.changes.iter().find(|change| change.position == position) {
+            self.mined_this_turn = self.mined_this_turn.saturating_sub(change.old.saturating_sub(change.new));
+        }
+    }
+
+    // Returns the halite the cell had. A cell changed more than once in a turn keeps one
+    // entry in `changes`, with the halite it had when the turn began.
+    fn replace_halite(&mut self, position: &Position, halite: usize) -> usize {
+        let position = self.normalize(position);
+        let cell = &mut self.cells[position.y as usize][position.x as usize];
+        let old = cell.halite;
+        cell.halite = halite;
+        self.current_halite = self.current_halite + halite - old;
+
+        match self.changes.iter().position(|change| change.position == position) {
+            Some(i) if self.changes[i].old == halite => {
+                self.changes.remove(i);
+            }
+            Some(i) => self.changes[i].new = halite,
+            None if old != halite => self.changes.push(CellChange { position, old, new: halite }),
+            None => (),
+        }
+        old
+    }
+
+    pub fn check_position(&self, position: &Position) -> Result<(), ProtocolError> {
+        if position.x < 0 || position.x as usize >= self.width {
+            return Err(ProtocolError::OutOfRange { what: "x", value: position.x as i64 });
+        }
+        if position.y < 0 || position.y as usize >= self.height {
+            return Err(ProtocolError::OutOfRange { what: "y", value: position.y as i64 });
+        }
+        Ok(())
+    }
+
+    pub fn update(&mut self, input: &mut Input) -> Result<(), ProtocolError> {
+        input.read_and_parse_line()?;
+        let update_count = input.next_usize()?;
+        self.begin_turn();
 
         for _ in 0..update_count {
-            input.read_and_parse_line();
-            let x = input.next_usize();
-            let y = input.next_usize();
-            let halite = input.next_usize();
+            input.read_and_parse_line()?;
+            let x = input.next_usize()?;
+            let y = input.next_usize()?;
+            let halite = input.next_usize()?;
 
-            self.cells[y][x].halite = halite;
+            if x >= self.width {
+                return Err(ProtocolError::OutOfRange { what: "x", value: x as i64 });
+            }
+            if y >= self.height {
+                return Err(ProtocolError::OutOfRange { what: "y", value: y as i64 });
+            }
+            self.set_halite(&Position { x: x as i32, y: y as i32 }, halite);
         }
+        Ok(())
     }
 
-    pub fn generate(input: &mut Input) -> GameMap {
-        input.read_and_parse_line();
-        let width = input.next_usize();
-        let height = input.next_usize();
-        let mut total_halite = 0;
+    pub fn generate(input: &mut Input) -> Result<GameMap, ProtocolError> {
+        input.read_and_parse_line()?;
+        let width = input.next_usize()?;
+        let height = input.next_usize()?;
 
         let mut cells: Vec<Vec<MapCell>> = Vec::with_capacity(height);
         for y in 0..height {
-            input.read_and_parse_line();
+            input.read_and_parse_line()?;
 
             let mut row: Vec<MapCell> = Vec::with_capacity(width);
             for x in 0..width {
-                let halite = input.next_usize();
-                total_halite += halite;
+                let halite = input.next_usize()?;
 
                 let position = Position { x: x as i32, y: y as i32 };
                 let cell = MapCell { position, halite, structure: Structure::None };
@@ -89,6 +188,46 @@ impl GameMap {
             cells.push(row);
         }
 
-        GameMap { width, height, total_halite, cells }
+        Ok(GameMap::new(width, height, cells))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::game_map::GameMap;
+    use hlt::map_cell::MapCell;
+    use hlt::map_cell::Structure;
+    use hlt::position::Position;
+
+    fn map() -> GameMap {
+        let cells = (0..2)
+            .map(|y| (0..2).map(|x| MapCell { position: Position { x, y }, halite: 100, structure: Structure::None }).collect())
+            .collect();
+        GameMap::new(2, 2, cells)
+    }
+
+    #[test]
+    fn changes_to_one_cell_are_coalesced() {
+        let mut map = map();
+        let position = Position { x: 1, y: 0 };
+        map.set_halite(&position, 150);
+        map.set_halite(&position, 112);
+        assert_eq!(map.changes.len(), 1);
+        assert_eq!((map.changes[0].old, map.changes[0].new), (100, 112));
+        assert_eq!((map.mined_this_turn, map.dropped_this_turn), (38, 50));
+        assert_eq!(map.current_halite, 412);
+
+        map.set_halite(&position, 100);
+        assert!(map.changes.is_empty());
+        assert_eq!(map.current_halite, 400);
+    }
+
+    #[test]
+    fn clearing_a_cell_is_not_mining() {
+        let mut map = map();
+        map.clear_halite(&Position { x: 0, y: 1 });
+        assert_eq!((map.mined_this_turn, map.dropped_this_turn), (0, 0));
+        assert_eq!(map.current_halite, 300);
+        assert_eq!(map.changes.len(), 1);
     }
 }
@@ -14,4 +14,5 @@ pub struct GradientCell {
     pub cells_effecting: i64,
     pub local_maxim: bool,
     pub enemy_predicted_halite: isize,
+    pub capture_risk: bool,
 }
\ No newline at end of file
@@ -1,10 +1,13 @@
 use hlt::direction::Direction;
+use hlt::engine::Engine;
 use hlt::game::Game;
 use hlt::gradient_cell::GradientCell;
 use hlt::navi::Navi;
 use hlt::position::Position;
+use hlt::rng;
 use hlt::ship::Ship;
 use hlt::ShipId;
+use rand::prng::XorShiftRng;
 use std::cmp::Ordering;
 use std::collections::BinaryHeap;
 
@@ -35,10 +38,10 @@ pub struct GradientMap {
 }
 
 impl GradientMap {
-    pub fn construct(game: &Game) -> GradientMap {
+    pub fn construct(game: &Game, rng: &mut XorShiftRng) -> GradientMap {
         let height = game.map.height;
         let width = game.map.width;
-        let mut halite_remaining = 0;
+        let halite_remaining = game.map.current_halite;
         let value_max_heap = BinaryHeap::new();
         let me = &game.players[game.my_id.0];
         let shipyard_pos = &me.shipyard.position;
@@ -60,28 +63,32 @@ impl GradientMap {
                     y: y as i32,
                 };
                 let cell_halite: usize = game.map.at_position(&position).halite;
-                halite_remaining += cell_halite;
 
-                let collection_amt: f64 = cell_halite as f64 / 4 as f64;
+                let collection_amt: f64 = cell_halite as f64 / game.constants.extract_ratio as f64;
                 let value: f64 = collection_amt;
-                let move_cost: f64 = cell_halite as f64 / 10 as f64;
+                let move_cost: f64 = cell_halite as f64 / game.constants.move_cost_ratio as f64;
                 let nearby_ship_count: i8 = 0;
                 let my_ship_count: i8 = 0;
                 let surrounding_average: f64 = 0.0;
                 let my_occupy = false;
                 let cells_effecting: i64 = 0;
                 let enemy_predicted_halite: isize = 0;
+                let capture_risk = false;
 
                 let mut nearest_dropoff = Position {
                     x: shipyard_pos.x,
                     y: shipyard_pos.y,
                 };
                 let mut distance_to_dropoff = width;
+                let mut ties = 0;
                 for pos in &dropoffs {
                     let interm_d = position.distance_to(pos, &width, &height);
-                    if interm_d <= distance_to_dropoff {
+                    if interm_d < distance_to_dropoff {
                         nearest_dropoff = pos.clone();
                         distance_to_dropoff = interm_d;
+                        ties = 1;
+                    } else if interm_d == distance_to_dropoff && rng::take_tie(rng, &mut ties) {
+                        nearest_dropoff = *pos;
                     }
                 }
 
@@ -100,7 +107,8 @@ impl GradientMap {
                     my_ship_count,
                     cells_effecting,
                     local_maxim,
-                    enemy_predicted_halite
+                    enemy_predicted_halite,
+                    capture_risk,
                 };
 
                 row.push(cell);
@@ -163,11 +171,14 @@ impl GradientMap {
         self.at_position_mut(&ship.position).my_occupy = true;
     }
 
-    pub fn initialize(&mut self, game: &Game, navi: &Navi) {
+    pub fn initialize(&mut self, game: &Game, navi: &Navi, rng: &mut XorShiftRng) {
         let max = self.height / 8 + 1;
         let rad = self.height / 20 + 1;
         self.adjust_cells_for_adjacent_ship_entities(&game);
-        self.predict_enemy_movement(game, navi);
+        self.predict_enemy_movement(game, navi, rng);
+        if game.constants.capture_enabled {
+            self.adjust_for_capture(game);
+        }
         self.smoothing(navi);
         self.find_local_maxims(navi, rad as i32, max);
         self.adjust_for_bullshit_on_my_shipyard(&game);
@@ -205,14 +216,15 @@ impl GradientMap {
     }
 
     fn adjust_cells_for_adjacent_ship_entities(&mut self, game: &Game) {
+        let radius = game.constants.inspiration_radius as i32;
         // for each ship
         for enemy_player in &game.enemy_players() {
             for enemy_ship_id in &enemy_player.ship_ids {
                 let ship = &game.ships[enemy_ship_id];
-                //loop over 4-radius and increase ship_count on gradient cell
+                //loop over inspiration radius and increase ship_count on gradient cell
 
-                for j in -4..4 {
-                    for i in -4..4 {
+                for j in -radius..radius {
+                    for i in -radius..radius {
                         let current_position = Position {
                             x: ship.position.x + i as i32,
                             y: ship.position.y + j as i32,
@@ -228,10 +240,10 @@ impl GradientMap {
 
         for ship_id in &game.players[game.my_id.0].ship_ids {
             let ship = &game.ships[ship_id];
-            //loop over 4-radius and increase ship_count on gradient cell
+            //loop over inspiration radius and increase ship_count on gradient cell
 
-            for j in -4..4 {
-                for i in -4..4 {
+            for j in -radius..radius {
+                for i in -radius..radius {
                     let current_position = Position {
                         x: ship.position.x + i as i32,
                         y: ship.position.y + j as i32,
@@ -244,20 +256,23 @@ impl GradientMap {
             }
         }
 
-        // for each gradient cell increase value if nearby_ship_count is greater than 2
+        // for each gradient cell increase value if enough enemy ships are nearby to inspire
+        if !game.constants.inspiration_enabled {
+            return;
+        }
         for cell in self.cells.iter_mut().flatten() {
-            if cell.nearby_ship_count > 1 {
-                cell.value += cell.collection_amt * 2.0;
+            if cell.nearby_ship_count as usize >= game.constants.inspiration_ship_count {
+                cell.value += cell.collection_amt * game.constants.inspired_bonus_multiplier;
             }
         }
     }
 
-    fn predict_enemy_movement(&mut self, game: &Game, navi: &Navi) {
+    fn predict_enemy_movement(&mut self, game: &Game, navi: &Navi, rng: &mut XorShiftRng) {
         for enemy_player in &game.enemy_players() {
             for enemy_ship_id in &enemy_player.ship_ids {
                 let ship = &game.ships[enemy_ship_id];
-                if ship.halite < 900 {
-                    let mut direction_vec = navi.get_possible_gather_move_vector(self, &ship.position, ship, true, false);
+                if ship.halite < game.constants.max_halite * 9 / 10 {
+                    let mut direction_vec = navi.get_possible_gather_move_vector(self, &ship.position, ship, true, false, rng);
                     let mut direction = Direction::Still;
                     if direction_vec.len() > 0 {
                         direction = direction_vec.pop().unwrap();
@@ -286,6 +301,45 @@ impl GradientMap {
         }
     }
 
+    // marks cells where one of our ships would be captured and pulls ships towards enemy
+    // ships that one more of ours nearby would capture
+    fn adjust_for_capture(&mut self, game: &Game) {
+        let radius = game.constants.capture_radius;
+        let ships_above = game.constants.ships_above_for_capture;
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let position = Position { x: x as i32, y: y as i32 };
+                let mut counts = Engine::ships_within(game, &position, radius);
+                // a ship moving here counts for us even if it is coming from outside the radius
+                counts[game.my_id.0] = counts[game.my_id.0].max(1);
+                self.cells[y][x].capture_risk = Engine::capturer(&counts, game.my_id, ships_above).is_some();
+            }
+        }
+
+        for enemy_player in &game.enemy_players() {
+            for enemy_ship_id in &enemy_player.ship_ids {
+                let ship = &game.ships[enemy_ship_id];
+                let mut counts = Engine::ships_within(game, &ship.position, radius);
+                counts[game.my_id.0] += 1;
+                if Engine::capturer(&counts, ship.owner, ships_above) != Some(game.my_id) {
+                    continue;
+                }
+
+                let bonus = (ship.halite + game.constants.ship_cost) as f64 / 4.0;
+                for dy in -(radius as i32)..=(radius as i32) {
+                    for dx in -(radius as i32)..=(radius as i32) {
+                        if dx.abs() + dy.abs() > radius as i32 || (dx == 0 && dy == 0) {
+                            continue;
+                        }
+                        let position = Position { x: ship.position.x + dx, y: ship.position.y + dy };
+                        self.at_position_mut(&position).value += bonus;
+                    }
+                }
+            }
+        }
+    }
+
     //makes each cell value an average of the others
     fn smoothing(&mut self, navi: &Navi) {
         let rad = self.width / 8 + 2;
@@ -383,7 +437,7 @@ impl GradientMap {
 
     fn adjust_for_distance(&mut self, game: &Game) {
         let L = game.turn_number as f64 / game.constants.max_turns as f64;
-        let percent_h_r = self.halite_remaining as f64 / game.map.total_halite as f64;
+        let percent_h_r = self.halite_remaining as f64 / game.map.initial_halite as f64;
         for y in 0..self.height as i32 {
             for x in 0..self.width as i32 {
                 let position = Position { x, y };
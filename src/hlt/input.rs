@@ -1,56 +1,59 @@
-use hlt::log::Log;
-use std::io::stdin;
-use std::process::exit;
+use hlt::protocol_error::ProtocolError;
+use std::any::type_name;
+use std::io;
+use std::io::BufRead;
 use std::str::FromStr;
 
 pub struct Input {
+    reader: Box<dyn BufRead>,
     tokens: Vec<String>,
     current_token: usize,
 }
 
 impl Input {
-    pub fn new() -> Input {
-        Input { tokens: Vec::new(), current_token: 0 }
+    pub fn new(reader: Box<dyn BufRead>) -> Input {
+        Input { reader, tokens: Vec::new(), current_token: 0 }
     }
 
-    pub fn read_and_return_line(&mut self) -> String {
+    // For games that are never fed frames, like the views handed to in-process bots.
+    pub fn empty() -> Input {
+        Input::new(Box::new(io::empty()))
+    }
+
+    pub fn read_and_return_line(&mut self) -> Result<String, ProtocolError> {
         let mut buf = String::new();
-        match stdin().read_line(&mut buf) {
-            Ok(_) => (),
-            Err(_) => {
-                Log::log("Input connection from server closed. Exiting...");
-                Log::flush();
-                exit(0);
-            },
+        match self.reader.read_line(&mut buf) {
+            Ok(0) => Err(ProtocolError::Closed),
+            Ok(_) => Ok(buf),
+            Err(e) => Err(ProtocolError::Read(e.to_string())),
         }
-        buf
     }
 
-    pub fn read_and_parse_line(&mut self) {
-        let buf = self.read_and_return_line();
+    pub fn read_and_parse_line(&mut self) -> Result<(), ProtocolError> {
+        let buf = self.read_and_return_line()?;
         let token_iter = buf.split_whitespace();
         let token_iter = token_iter.filter(|x| !x.is_empty());
         let token_iter = token_iter.map(|x| x.to_string());
         self.tokens = token_iter.collect();
         self.current_token = 0;
+        Ok(())
     }
 
-    pub fn next_i32(&mut self) -> i32 {
+    pub fn next_i32(&mut self) -> Result<i32, ProtocolError> {
         self.next()
     }
 
-    pub fn next_usize(&mut self) -> usize {
+    pub fn next_usize(&mut self) -> Result<usize, ProtocolError> {
         self.next()
     }
 
-    pub fn next<T: FromStr>(&mut self) -> T {
-        let token = &self.tokens[self.current_token];
+    pub fn next<T: FromStr>(&mut self) -> Result<T, ProtocolError> {
+        let token = match self.tokens.get(self.current_token) {
+            Some(token) => token,
+            None => return Err(ProtocolError::MissingToken { line: self.tokens.join(" ") }),
+        };
         self.current_token += 1;
 
-        let result = match token.parse() {
-            Ok(x) => x,
-            Err(_) => Log::panic(&format!("Can't parse '{}'.", token)),
-        };
-        result
+        token.parse().map_err(|_| ProtocolError::BadToken { token: token.clone(), expected: type_name::<T>() })
     }
 }
@@ -0,0 +1,416 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Write;
+
+// Minimal JSON value used for the constants block and replay files. Objects keep their
+// keys sorted, which matches how the official engine serializes them.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Array(Vec<Json>),
+    Object(BTreeMap<String, Json>),
+}
+
+impl Json {
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = Parser { chars: text.chars().collect(), index: 0 };
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+        if parser.index != parser.chars.len() {
+            return Err(format!("unexpected trailing data at offset {}", parser.index));
+        }
+        Ok(value)
+    }
+
+    // Display fails on NaN and infinities, which JSON can't represent; this reports it
+    // as an error where to_string would panic.
+    pub fn to_text(&self) -> Result<String, String> {
+        let mut text = String::new();
+        write!(text, "{}", self).map_err(|_| "NaN and infinite numbers can't be written as JSON".to_string())?;
+        Ok(text)
+    }
+
+    pub fn object() -> Json {
+        Json::Object(BTreeMap::new())
+    }
+
+    pub fn with(mut self, key: &str, value: Json) -> Json {
+        if let Json::Object(ref mut map) = self {
+            map.insert(key.to_string(), value);
+        }
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Json::Int(x) => Some(x),
+            Json::Float(x) if x.fract() == 0.0 => Some(x as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        self.as_i64().filter(|x| *x >= 0).map(|x| x as usize)
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Json::Int(x) => Some(x as f64),
+            Json::Float(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Json::Bool(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::Str(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Json>> {
+        match self {
+            Json::Array(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&BTreeMap<String, Json>> {
+        match self {
+            Json::Object(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(x) => write!(f, "{}", x),
+            Json::Int(x) => write!(f, "{}", x),
+            Json::Float(x) if !x.is_finite() => Err(fmt::Error),
+            Json::Float(x) => write!(f, "{:?}", x),
+            Json::Str(x) => write_escaped(f, x),
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(map) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_escaped(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_escaped(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+}
+
+impl Parser {
+    fn skip_whitespace(&mut self) {
+        while self.index < self.chars.len() && self.chars[self.index].is_whitespace() {
+            self.index += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).cloned()
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.index += 1;
+            Ok(())
+        } else {
+            Err(format!("expected '{}' at offset {}", c, self.index))
+        }
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: Json) -> Result<Json, String> {
+        for expected in literal.chars() {
+            if self.peek() != Some(expected) {
+                return Err(format!("invalid literal at offset {}", self.index));
+            }
+            self.index += 1;
+        }
+        Ok(value)
+    }
+
+    fn parse_value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.parse_object(),
+            Some('[') => self.parse_array(),
+            Some('"') => self.parse_string().map(Json::Str),
+            Some('t') => self.parse_literal("true", Json::Bool(true)),
+            Some('f') => self.parse_literal("false", Json::Bool(false)),
+            Some('n') => self.parse_literal("null", Json::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number(),
+            Some(c) => Err(format!("unexpected '{}' at offset {}", c, self.index)),
+            None => Err("unexpected end of input".to_string()),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Json, String> {
+        self.expect('{')?;
+        let mut map = BTreeMap::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.index += 1;
+            return Ok(Json::Object(map));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.expect(':')?;
+            let value = self.parse_value()?;
+            map.insert(key, value);
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.index += 1,
+                Some('}') => {
+                    self.index += 1;
+                    return Ok(Json::Object(map));
+                }
+                _ => return Err(format!("expected ',' or '}}' at offset {}", self.index)),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Json, String> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.index += 1;
+            return Ok(Json::Array(values));
+        }
+
+        loop {
+            values.push(self.parse_value()?);
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.index += 1,
+                Some(']') => {
+                    self.index += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => return Err(format!("expected ',' or ']' at offset {}", self.index)),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        if self.peek() != Some('"') {
+            return Err(format!("expected string at offset {}", self.index));
+        }
+        self.index += 1;
+
+        let mut s = String::new();
+        loop {
+            let c = match self.peek() {
+                Some(c) => c,
+                None => return Err("unterminated string".to_string()),
+            };
+            self.index += 1;
+
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escaped = self.peek().ok_or_else(|| "unterminated escape".to_string())?;
+                    self.index += 1;
+                    match escaped {
+                        '"' => s.push('"'),
+                        '\\' => s.push('\\'),
+                        '/' => s.push('/'),
+                        'b' => s.push('\u{8}'),
+                        'f' => s.push('\u{c}'),
+                        'n' => s.push('\n'),
+                        'r' => s.push('\r'),
+                        't' => s.push('\t'),
+                        'u' => s.push(self.parse_unicode_escape()?),
+                        _ => return Err(format!("invalid escape at offset {}", self.index)),
+                    }
+                }
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn parse_unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.parse_hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if self.peek() != Some('\\') || self.chars.get(self.index + 1) != Some(&'u') {
+                return Err(format!("unpaired surrogate at offset {}", self.index));
+            }
+            self.index += 2;
+            let low = self.parse_hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(format!("unpaired surrogate at offset {}", self.index));
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+        std::char::from_u32(code).ok_or_else(|| format!("invalid code point at offset {}", self.index))
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, String> {
+        if self.index + 4 > self.chars.len() {
+            return Err("truncated unicode escape".to_string());
+        }
+        let hex: String = self.chars[self.index..self.index + 4].iter().collect();
+        self.index += 4;
+        u32::from_str_radix(&hex, 16).map_err(|_| format!("invalid unicode escape '{}'", hex))
+    }
+
+    fn parse_number(&mut self) -> Result<Json, String> {
+        let start = self.index;
+        let mut is_float = false;
+        while let Some(c) = self.peek() {
+            match c {
+                '0'..='9' | '-' | '+' => (),
+                '.' | 'e' | 'E' => is_float = true,
+                _ => break,
+            }
+            self.index += 1;
+        }
+
+        let text: String = self.chars[start..self.index].iter().collect();
+        if is_float {
+            match text.parse::<f64>() {
+                Ok(x) if x.is_finite() => Ok(Json::Float(x)),
+                _ => Err(format!("invalid number '{}'", text)),
+            }
+        } else {
+            text.parse().map(Json::Int).map_err(|_| format!("invalid number '{}'", text))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::json::Json;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn parses_escapes_and_surrogate_pairs() {
+        let parsed = Json::parse(r#""a\"b\\c\/d\b\f\n\r\t\u00e9\ud83d\ude00""#).unwrap();
+        assert_eq!(parsed, Json::Str("a\"b\\c/d\u{8}\u{c}\n\r\t\u{e9}\u{1f600}".to_string()));
+
+        assert!(Json::parse(r#""\ud83d""#).is_err());
+        assert!(Json::parse(r#""\ud83d\u0041""#).is_err());
+        assert!(Json::parse(r#""\ude00""#).is_err());
+        assert!(Json::parse(r#""\x""#).is_err());
+        assert!(Json::parse(r#""\u12""#).is_err());
+    }
+
+    #[test]
+    fn escapes_round_trip() {
+        let text = "quote \" backslash \\ newline \n tab \t bell \u{7} e\u{301}".to_string();
+        let written = Json::Str(text.clone()).to_string();
+        assert_eq!(written, "\"quote \\\" backslash \\\\ newline \\n tab \\t bell \\u0007 e\u{301}\"");
+        assert_eq!(Json::parse(&written).unwrap(), Json::Str(text));
+    }
+
+    #[test]
+    fn parses_numbers() {
+        assert_eq!(Json::parse("-12").unwrap(), Json::Int(-12));
+        assert_eq!(Json::parse("0.25").unwrap(), Json::Float(0.25));
+        assert_eq!(Json::parse("-1.5").unwrap(), Json::Float(-1.5));
+        assert_eq!(Json::parse("2e3").unwrap(), Json::Float(2000.0));
+        assert_eq!(Json::parse("1.5E-2").unwrap(), Json::Float(0.015));
+        assert_eq!(Json::parse("2e3").unwrap().as_usize(), Some(2000));
+        assert_eq!(Json::parse("-3").unwrap().as_usize(), None);
+
+        assert!(Json::parse("1e999").is_err());
+        assert!(Json::parse("1.2.3").is_err());
+        assert!(Json::parse("-").is_err());
+    }
+
+    #[test]
+    fn rejects_trailing_data() {
+        assert!(Json::parse("1 2").is_err());
+        assert!(Json::parse("{}}").is_err());
+        assert!(Json::parse("[1],").is_err());
+        assert!(Json::parse("truex").is_err());
+        assert_eq!(Json::parse(" [1] \n").unwrap(), Json::Array(vec![Json::Int(1)]));
+    }
+
+    #[test]
+    fn parses_nested_empty_containers() {
+        let parsed = Json::parse(r#"{"a":[[],{}],"b":{"c":[]}}"#).unwrap();
+        let expected = Json::object()
+            .with("a", Json::Array(vec![Json::Array(vec![]), Json::Object(BTreeMap::new())]))
+            .with("b", Json::object().with("c", Json::Array(vec![])));
+        assert_eq!(parsed, expected);
+        assert_eq!(parsed.to_string(), r#"{"a":[[],{}],"b":{"c":[]}}"#);
+
+        assert!(Json::parse("[,]").is_err());
+        assert!(Json::parse("{,}").is_err());
+        assert!(Json::parse("[1,]").is_err());
+    }
+
+    #[test]
+    fn refuses_to_write_non_finite_numbers() {
+        assert_eq!(Json::Float(2.0).to_text().unwrap(), "2.0");
+        for x in &[f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let nested = Json::object().with("list", Json::Array(vec![Json::Int(1), Json::Float(*x)]));
+            assert!(nested.to_text().is_err());
+        }
+    }
+}
@@ -12,11 +12,12 @@ lazy_static! {
 pub struct Log {
     log_buffer: Option<Vec<String>>,
     file: Option<File>,
+    muted: bool,
 }
 
 impl Log {
     pub fn new() -> Log {
-        Log { log_buffer: Some(Vec::new()), file: None }
+        Log { log_buffer: Some(Vec::new()), file: None, muted: false }
     }
 
     pub fn open(bot_id: usize) {
@@ -35,9 +36,20 @@ impl Log {
         log.log_buffer = None;
     }
 
+    // Drops every later message, for running many bots inside one process.
+    pub fn mute() {
+        let mut log = LOG.lock().unwrap();
+        log.muted = true;
+        log.log_buffer = Some(Vec::new());
+    }
+
     pub fn log(message: &str) {
         let mut log = LOG.lock().unwrap();
 
+        if log.muted {
+            return;
+        }
+
         match &mut log.file {
             Some(file) => {
                 writeln!(file, "{}", message).unwrap();
@@ -0,0 +1,252 @@
+use hlt::constants::Constants;
+use hlt::game::Game;
+use hlt::game_map::GameMap;
+use hlt::map_cell::MapCell;
+use hlt::map_cell::Structure;
+use hlt::player::Player;
+use hlt::position::Position;
+use hlt::rng;
+use hlt::shipyard::Shipyard;
+use hlt::PlayerId;
+use rand::prng::XorShiftRng;
+use rand::Rng;
+
+// Defaults of the official generator, see the PERSISTENCE, FACTOR_EXP_* and
+// *_CELL_PRODUCTION keys in the constants block.
+const PERSISTENCE: f64 = 0.7;
+const FACTOR_EXP_1: f64 = 2.0;
+const FACTOR_EXP_2: f64 = 2.0;
+const MIN_CELL_PRODUCTION: usize = 900;
+const MAX_CELL_PRODUCTION: usize = 1000;
+
+pub struct GeneratedMap {
+    pub map: GameMap,
+    pub shipyards: Vec<Position>,
+}
+
+impl GeneratedMap {
+    pub fn players(&self, initial_halite: usize) -> Vec<Player> {
+        self.shipyards
+            .iter()
+            .enumerate()
+            .map(|(i, position)| Player {
+                id: PlayerId(i),
+                shipyard: Shipyard { owner: PlayerId(i), position: *position },
+                halite: initial_halite,
+                ship_ids: Vec::new(),
+                dropoff_ids: Vec::new(),
+            })
+            .collect()
+    }
+}
+
+// Fractal value noise generator in the style of the official one. One tile of noise is
+// generated per player and mirrored across the map so every player sees the same
+// surroundings; the same seed, size and player count always give the same map.
+// It does not reproduce the official generator's maps: a seed pinned in run_game.sh
+// gives a different map here than on the official server.
+pub struct MapGenerator {
+    rng: XorShiftRng,
+}
+
+impl MapGenerator {
+    pub fn new(seed: u64) -> MapGenerator {
+        MapGenerator { rng: rng::seeded(seed) }
+    }
+
+    pub fn generate(&mut self, width: usize, height: usize, num_players: usize) -> Result<GeneratedMap, String> {
+        if num_players != 2 && num_players != 4 {
+            return Err(format!("maps are generated for 2 or 4 players, not {}", num_players));
+        }
+        if width == 0 || height == 0 || !width.is_multiple_of(2) || !height.is_multiple_of(2) {
+            return Err(format!("map dimensions must be even and non-zero, not {}x{}", width, height));
+        }
+
+        let tile_width = width / 2;
+        let tile_height = if num_players == 4 { height / 2 } else { height };
+        let tile = self.generate_tile(tile_width, tile_height);
+
+        let mut cells: Vec<Vec<MapCell>> = Vec::with_capacity(height);
+        for y in 0..height {
+            let mut row: Vec<MapCell> = Vec::with_capacity(width);
+            for x in 0..width {
+                let tile_x = if x < tile_width { x } else { width - 1 - x };
+                let tile_y = if y < tile_height { y } else { height - 1 - y };
+                let halite = tile[tile_y][tile_x];
+
+                let position = Position { x: x as i32, y: y as i32 };
+                row.push(MapCell { position, halite, structure: Structure::None });
+            }
+            cells.push(row);
+        }
+
+        let yard_x = (tile_width / 2) as i32;
+        let yard_y = (tile_height / 2) as i32;
+        let mirror_x = width as i32 - 1 - yard_x;
+        let mirror_y = height as i32 - 1 - yard_y;
+        let mut shipyards = vec![Position { x: yard_x, y: yard_y }, Position { x: mirror_x, y: yard_y }];
+        if num_players == 4 {
+            shipyards.push(Position { x: yard_x, y: mirror_y });
+            shipyards.push(Position { x: mirror_x, y: mirror_y });
+        }
+
+        for position in &shipyards {
+            cells[position.y as usize][position.x as usize].halite = 0;
+        }
+
+        Ok(GeneratedMap { map: GameMap::new(width, height, cells), shipyards })
+    }
+
+    pub fn generate_game(
+        &mut self,
+        width: usize,
+        height: usize,
+        num_players: usize,
+        constants: Constants,
+        initial_halite: usize,
+    ) -> Result<Game, String> {
+        let generated = self.generate(width, height, num_players)?;
+        let players = generated.players(initial_halite);
+        Ok(Game::construct(constants, PlayerId(0), players, generated.map))
+    }
+
+    fn generate_tile(&mut self, width: usize, height: usize) -> Vec<Vec<usize>> {
+        let source: Vec<Vec<f64>> = (0..height)
+            .map(|_| (0..width).map(|_| self.rng.gen::<f64>()).collect())
+            .collect();
+
+        let mut octaves = 0;
+        while (1 << octaves) < width.max(height) {
+            octaves += 1;
+        }
+
+        let mut region = vec![vec![0.0; width]; height];
+        let mut amplitude = 1.0;
+        for octave in (0..octaves).rev() {
+            let noise = MapGenerator::smooth_noise(&source, 1 << octave);
+            for y in 0..height {
+                for x in 0..width {
+                    region[y][x] += amplitude * noise[y][x];
+                }
+            }
+            amplitude *= PERSISTENCE;
+        }
+
+        let mut max_value: f64 = 0.0;
+        for value in region.iter_mut().flatten() {
+            *value = value.powf(FACTOR_EXP_1);
+            max_value = max_value.max(*value);
+        }
+
+        let max_production = self.rng.gen_range(MIN_CELL_PRODUCTION, MAX_CELL_PRODUCTION + 1) as f64;
+        region
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|value| {
+                        let normalized = if max_value > 0.0 { value / max_value } else { 0.0 };
+                        (normalized.powf(FACTOR_EXP_2) * max_production).round() as usize
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Samples the source noise every `wavelength` cells and bilinearly interpolates
+    // between the samples with a smoothstep curve.
+    fn smooth_noise(source: &[Vec<f64>], wavelength: usize) -> Vec<Vec<f64>> {
+        let height = source.len();
+        let width = source[0].len();
+        let frequency = 1.0 / wavelength as f64;
+        let smoothstep = |t: f64| t * t * (3.0 - 2.0 * t);
+
+        let mut noise = vec![vec![0.0; width]; height];
+        for (y, row) in noise.iter_mut().enumerate() {
+            let y0 = (y / wavelength) * wavelength;
+            let y1 = (y0 + wavelength) % height;
+            let vertical = smoothstep((y - y0) as f64 * frequency);
+
+            for (x, value) in row.iter_mut().enumerate() {
+                let x0 = (x / wavelength) * wavelength;
+                let x1 = (x0 + wavelength) % width;
+                let horizontal = smoothstep((x - x0) as f64 * frequency);
+
+                let top = source[y0][x0] * (1.0 - horizontal) + source[y0][x1] * horizontal;
+                let bottom = source[y1][x0] * (1.0 - horizontal) + source[y1][x1] * horizontal;
+                *value = top * (1.0 - vertical) + bottom * vertical;
+            }
+        }
+        noise
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::game_map::GameMap;
+    use hlt::map_generator::MapGenerator;
+    use hlt::position::Position;
+
+    fn halite(map: &GameMap) -> Vec<usize> {
+        map.cells.iter().flatten().map(|cell| cell.halite).collect()
+    }
+
+    // Halite within `radius` of the position.
+    fn halite_around(map: &GameMap, center: &Position, radius: usize) -> usize {
+        map.cells
+            .iter()
+            .flatten()
+            .filter(|cell| map.calculate_distance(center, &cell.position) <= radius)
+            .map(|cell| cell.halite)
+            .sum()
+    }
+
+    #[test]
+    fn a_seed_always_gives_the_same_map() {
+        let first = MapGenerator::new(1548006861).generate(32, 32, 2).unwrap();
+        let second = MapGenerator::new(1548006861).generate(32, 32, 2).unwrap();
+        let other = MapGenerator::new(1548006862).generate(32, 32, 2).unwrap();
+
+        assert_eq!(halite(&first.map), halite(&second.map));
+        assert!(first.shipyards == second.shipyards);
+        assert_ne!(halite(&first.map), halite(&other.map));
+    }
+
+    #[test]
+    fn every_player_has_the_same_surroundings() {
+        for (size, num_players) in &[(32, 2), (48, 2), (40, 4), (64, 4)] {
+            let generated = MapGenerator::new(7).generate(*size, *size, *num_players).unwrap();
+            let map = &generated.map;
+            for radius in &[1, 4, 8, 16] {
+                let around: Vec<usize> =
+                    generated.shipyards.iter().map(|shipyard| halite_around(map, shipyard, *radius)).collect();
+                assert!(around.iter().all(|x| *x == around[0]), "{}x{} radius {}: {:?}", size, size, radius, around);
+            }
+            for shipyard in &generated.shipyards {
+                assert_eq!(map.at_position(shipyard).halite, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn official_sizes_and_player_counts_are_supported() {
+        for size in &[32, 40, 48, 56, 64] {
+            for num_players in &[2, 4] {
+                let generated = MapGenerator::new(3).generate(*size, *size, *num_players).unwrap();
+                assert_eq!((generated.map.width, generated.map.height), (*size, *size));
+                assert_eq!(generated.map.cells.len(), *size);
+                assert!(generated.map.cells.iter().all(|row| row.len() == *size));
+                assert_eq!(generated.shipyards.len(), *num_players);
+                assert!(generated.map.initial_halite > 0);
+                assert!(halite(&generated.map).iter().all(|x| *x <= 1000));
+            }
+        }
+    }
+
+    #[test]
+    fn other_sizes_and_player_counts_are_refused() {
+        assert!(MapGenerator::new(3).generate(32, 32, 3).is_err());
+        assert!(MapGenerator::new(3).generate(32, 32, 1).is_err());
+        assert!(MapGenerator::new(3).generate(33, 32, 2).is_err());
+        assert!(MapGenerator::new(3).generate(0, 0, 2).is_err());
+    }
+}
@@ -30,6 +30,57 @@ pub mod shipyard;
 pub mod gradient_cell;
 #[allow(dead_code)]
 pub mod gradient_map;
+#[allow(dead_code)]
+pub mod engine;
+#[allow(dead_code)]
+pub mod map_generator;
+#[allow(dead_code)]
+pub mod json;
+#[allow(dead_code)]
+pub mod replay_writer;
+#[allow(dead_code)]
+pub mod replay_reader;
+#[allow(dead_code)]
+pub mod protocol;
+#[allow(dead_code)]
+pub mod runner;
+#[allow(dead_code)]
+pub mod strategy;
+#[allow(dead_code)]
+pub mod bot;
+#[allow(dead_code)]
+pub mod arena;
+#[allow(dead_code)]
+pub mod forward_model;
+#[allow(dead_code)]
+pub mod tournament;
+#[allow(dead_code)]
+pub mod baseline;
+#[allow(dead_code)]
+pub mod prediction;
+#[allow(dead_code)]
+pub mod rule_presets;
+#[allow(dead_code)]
+pub mod what_if;
+#[allow(dead_code)]
+pub mod game_event;
+#[allow(dead_code)]
+pub mod snapshot;
+#[allow(dead_code)]
+pub mod rng;
+#[allow(dead_code)]
+pub mod protocol_error;
+#[allow(dead_code)]
+pub mod transcript;
+#[allow(dead_code)]
+pub mod ship_registry;
+#[allow(dead_code)]
+pub mod turn_inference;
+#[allow(dead_code)]
+pub mod scoreboard;
+
+#[cfg(test)]
+pub mod test_game;
 
 #[allow(dead_code)]
 mod input;
@@ -4,8 +4,11 @@ use hlt::command::Command;
 use hlt::gradient_map::GradientMap;
 use hlt::map_cell::MapCell;
 use hlt::position::Position;
+use hlt::rng;
 use hlt::ship::Ship;
 use hlt::ShipId;
+use rand::prng::XorShiftRng;
+use rand::Rng;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 
@@ -23,8 +26,10 @@ pub struct Navi {
     pub halite_per_cell_per_player: f64,
     pub dropoffs: usize,
     pub min_distance_ratio_for_map: f64,
-    pub this_turn_dropoff: bool
-
+    pub this_turn_dropoff: bool,
+    pub max_halite: usize,
+    pub extract_ratio: usize,
+    pub move_cost_ratio: usize,
 }
 
 impl Navi {
@@ -45,7 +50,7 @@ impl Navi {
             manhatten_points.insert(i, vec.clone());
         }
 
-        let halite_per_cell_per_player = game.map.total_halite as f64 / game.map.width as f64 / game.players.len() as f64;
+        let halite_per_cell_per_player = game.map.current_halite as f64 / game.map.width as f64 / game.players.len() as f64;
 
         let min_distance_ratio_for_map = match game.map.width as i32 {
             32 => 0.45,
@@ -71,6 +76,9 @@ impl Navi {
             dropoffs,
             min_distance_ratio_for_map,
             this_turn_dropoff,
+            max_halite: game.constants.max_halite,
+            extract_ratio: game.constants.extract_ratio,
+            move_cost_ratio: game.constants.move_cost_ratio,
         }
     }
 
@@ -114,6 +122,13 @@ impl Navi {
         }
     }
 
+    // Drops what we kept about a ship that is gone.
+    pub fn forget_ship(&mut self, ship_id: ShipId) {
+        self.end_game.remove(&ship_id);
+        self.time_to_home.remove(&ship_id);
+        self.have_moved.remove(&ship_id);
+    }
+
     pub fn process_move(&mut self, ship_id: ShipId) {
         self.have_moved.insert(ship_id, true);
     }
@@ -143,6 +158,7 @@ impl Navi {
         gradient_map: &mut GradientMap,
         ship: &Ship,
         game: &Game,
+        rng: &mut XorShiftRng,
     ) -> Command {
         self.set_end_game(&ship, &gradient_map, &game);
         self.set_time_to_home(&ship, &game, &gradient_map);
@@ -156,18 +172,18 @@ impl Navi {
         }
 
         if self.time_to_home[&ship.id] || self.end_game[&ship.id] {
-            let direction = self.drop_off_move(&gradient_map, &ship, &game);
+            let direction = self.drop_off_move(&gradient_map, &ship, &game, rng);
             gradient_map.process_move(&ship.position, direction);
             return ship.move_ship(direction);
         } else {
-            let direction = self.gather_move(&gradient_map, &ship, &game);
+            let direction = self.gather_move(&gradient_map, &ship, &game, rng);
             gradient_map.process_move(&ship.position, direction);
             return ship.move_ship(direction);
         }
     }
 
     pub fn its_convert_to_dropoff_time(&mut self, ship: &Ship, gradient_map: &GradientMap, game: &Game) -> bool {
-        let halite_c = 1.0 - (gradient_map.halite_remaining as f64 / game.map.total_halite as f64);
+        let halite_c = 1.0 - (gradient_map.halite_remaining as f64 / game.map.initial_halite as f64);
         let h_per_cell_per_player_per_dropoffs = self.halite_per_cell_per_player as f64 / (self.dropoffs + 1) as f64;
         let distance = gradient_map.at_position(&ship.position).distance_to_dropoff;
         let myships = gradient_map.at_position(&ship.position).my_ship_count;
@@ -177,7 +193,7 @@ impl Navi {
         if halite_c < 0.65
             && h_per_cell_per_player_per_dropoffs > 1000.0 
             && distance_ratio > self.min_distance_ratio_for_map
-            && game.players[game.my_id.0].halite + ship.halite >= 4000
+            && game.players[game.my_id.0].halite + ship.halite >= game.constants.dropoff_cost
             && myships >= their
             && self.dropoffs < game.players[game.my_id.0].ship_ids.len() / 10 {
             return true
@@ -187,7 +203,7 @@ impl Navi {
 
     fn set_time_to_home(&mut self, ship: &Ship, game: &Game, gradient_map: &GradientMap) {
         let nearest_dropoff = gradient_map.at_position(&ship.position).nearest_dropoff;
-        if Navi::is_stalled(ship, &game.map.at_position(&ship.position)) 
+        if self.is_stalled(ship, &game.map.at_position(&ship.position)) 
             || ship.position.same_position(&nearest_dropoff) {
         
             if let Some(x) = self.time_to_home.get_mut(&ship.id) {
@@ -207,7 +223,7 @@ impl Navi {
 
     fn will_time_to_home(&mut self, ship: &Ship, game: &Game, gradient_map: &GradientMap, new_position: &Position) {
         let nearest_dropoff = gradient_map.at_position(new_position).nearest_dropoff;
-        if Navi::is_stalled(ship, &game.map.at_position(&new_position))
+        if self.is_stalled(ship, &game.map.at_position(&new_position))
             || ship.position.same_position(&nearest_dropoff)
         {
             if let Some(x) = self.time_to_home.get_mut(&ship.id) {
@@ -227,9 +243,9 @@ impl Navi {
     fn worth_to_home(&self, halite: usize, gradient_map: &GradientMap, position: &Position) -> bool {
         let nearest_drop_off = gradient_map.at_position(position).nearest_dropoff;
         let distance = nearest_drop_off.distance_to(position, &self.width, &self.height);
-        let mut cutoff = (distance + 3) * 100;
-        if cutoff > 900 {
-            cutoff = 900;
+        let mut cutoff = (distance + 3) * self.max_halite / 10;
+        if cutoff > self.max_halite * 9 / 10 {
+            cutoff = self.max_halite * 9 / 10;
         }
         if halite > cutoff {
             return true
@@ -253,14 +269,14 @@ impl Navi {
     }
 
     // finds best direction, puts ship in stalled vector is necissary
-    fn gather_move(&mut self, gradient_map: &GradientMap, ship: &Ship, game: &Game) -> Direction {
-        let best_direction = self.determine_gather_move(gradient_map, ship, &game);
+    fn gather_move(&mut self, gradient_map: &GradientMap, ship: &Ship, game: &Game, rng: &mut XorShiftRng) -> Direction {
+        let best_direction = self.determine_gather_move(gradient_map, ship, &game, rng);
         let best_position = &ship.position.directional_offset(best_direction);
         let best_cell = game.map.at_position(best_position);
         let nearest_dropoff = gradient_map.at_position(&best_position).nearest_dropoff;
         let distance = gradient_map.at_position(&best_position).distance_to_dropoff;
 
-        if self.will_end_game(&best_cell.position, &game.turn_number, &game.constants.max_turns, &nearest_dropoff) || self.worth_to_home(ship.halite + best_cell.halite / 4, gradient_map, &best_cell.position) {
+        if self.will_end_game(&best_cell.position, &game.turn_number, &game.constants.max_turns, &nearest_dropoff) || self.worth_to_home(ship.halite + best_cell.halite / self.extract_ratio, gradient_map, &best_cell.position) {
             match distance {
                 0 => self.at_dropoff.push(ship.id),
                 _ => {
@@ -274,21 +290,21 @@ impl Navi {
                 }
             }
         } else {
-            self.prioritize_gather_ships_for_next_turn(ship, best_position, gradient_map, game);
+            self.prioritize_gather_ships_for_next_turn(ship, best_position, gradient_map, game, rng);
         }
         
         best_direction
     }
 
-    pub fn determine_gather_move(&self, gradient_map: &GradientMap, ship: &Ship, game: &Game) -> Direction {
+    pub fn determine_gather_move(&self, gradient_map: &GradientMap, ship: &Ship, game: &Game, rng: &mut XorShiftRng) -> Direction {
         let mut me_more = false;
         if game.ships.len() / (game.players.len() as usize) < game.players[game.my_id.0].ship_ids.len() {
             me_more = true;
         }
-        if Navi::is_stalled(ship, game.map.at_position(&ship.position)) {
+        if self.is_stalled(ship, game.map.at_position(&ship.position)) {
             return Direction::Still
         }
-        let mut possible_moves = self.get_possible_gather_move_vector(gradient_map, &ship.position, ship, false, me_more);
+        let mut possible_moves = self.get_possible_gather_move_vector(gradient_map, &ship.position, ship, false, me_more, rng);
         if possible_moves.len() > 0 {
             return  possible_moves.pop().unwrap()
         }
@@ -304,8 +320,8 @@ impl Navi {
         return weight + 0.1;
     }
 
-    fn drop_off_move(&mut self, gradient_map: &GradientMap, ship: &Ship, game: &Game) -> Direction {
-        let best_direction = self.determine_drop_off_move(&gradient_map, &ship, &game);
+    fn drop_off_move(&mut self, gradient_map: &GradientMap, ship: &Ship, game: &Game, rng: &mut XorShiftRng) -> Direction {
+        let best_direction = self.determine_drop_off_move(&gradient_map, &ship, &game, rng);
         let new_position = &ship.position.directional_offset(best_direction);
         let best_cell = gradient_map.at_position(new_position);
         let distance = best_cell.distance_to_dropoff;
@@ -331,11 +347,12 @@ impl Navi {
         gradient_map: &GradientMap,
         ship: &Ship,
         game: &Game,
+        rng: &mut XorShiftRng,
     ) -> Direction {
         let origin_position = ship.position;
         let origin_cell = gradient_map.at_position(&origin_position);
         let nearest_dropoff = origin_cell.nearest_dropoff;
-        if Navi::is_stalled(ship, game.map.at_position(&ship.position)) {
+        if self.is_stalled(ship, game.map.at_position(&ship.position)) {
             return Direction::Still
         }
 
@@ -346,7 +363,13 @@ impl Navi {
         }
 
         //this does not need to be a vector
-        let direction_vector = self.get_direct_move(&origin_position, &nearest_dropoff);
+        let mut direction_vector = self.get_direct_move(&origin_position, &nearest_dropoff);
+        // as far to go on both axes, either one is as good as the other
+        let dx = (origin_position.x - nearest_dropoff.x).unsigned_abs() as usize;
+        let dy = (origin_position.y - nearest_dropoff.y).unsigned_abs() as usize;
+        if direction_vector.len() == 2 && dx.min(self.width - dx) == dy.min(self.height - dy) && rng.gen() {
+            direction_vector.swap(0, 1);
+        }
 
         for direction in direction_vector {
             let potential_position = ship.position.directional_offset(direction);
@@ -358,7 +381,7 @@ impl Navi {
                     return direction;
                 }
             } else {
-                if potential_cell.my_occupy == false && ship.halite as f64 > origin_cell.move_cost {
+                if potential_cell.my_occupy == false && !potential_cell.capture_risk && ship.halite as f64 > origin_cell.move_cost {
                     return direction;
                 }
             }
@@ -410,8 +433,8 @@ impl Navi {
         possible_moves
     }
 
-    fn is_stalled(ship: &Ship, origin_cell: &MapCell) -> bool {
-        let stalled = if ship.halite < origin_cell.halite / 10 {
+    fn is_stalled(&self, ship: &Ship, origin_cell: &MapCell) -> bool {
+        let stalled = if ship.halite < origin_cell.halite / self.move_cost_ratio {
             true
         } else {
             false
@@ -419,8 +442,8 @@ impl Navi {
         stalled
     }
 
-    fn prioritize_gather_ships_for_next_turn(&mut self, ship: &Ship, next_position: &Position, gradient_map: &GradientMap, game: &Game) {
-        if self.at_peak(gradient_map, next_position, ship, game) || self.will_stall(ship, game.map.at_position(&ship.position), game.map.at_position(next_position)) {
+    fn prioritize_gather_ships_for_next_turn(&mut self, ship: &Ship, next_position: &Position, gradient_map: &GradientMap, game: &Game, rng: &mut XorShiftRng) {
+        if self.at_peak(gradient_map, next_position, ship, game, rng) || self.will_stall(ship, game.map.at_position(&ship.position), game.map.at_position(next_position)) {
             self.are_stalled.push(ship.id);
         } else {
             let distance = gradient_map.at_position(next_position).distance_to_dropoff;
@@ -444,14 +467,14 @@ impl Navi {
         
         let mut next_turn_ship_halite: isize  = 0;
         if current_cell.position.same_position(&next_cell.position) {
-            next_turn_ship_halite = ship.halite as isize + current_cell.halite as isize / 4;
+            next_turn_ship_halite = ship.halite as isize + current_cell.halite as isize / self.extract_ratio as isize;
         } else {
-            next_turn_ship_halite = ship.halite as isize - current_cell.halite as isize / 10;
+            next_turn_ship_halite = ship.halite as isize - current_cell.halite as isize / self.move_cost_ratio as isize;
         }
         return next_turn_ship_halite
     }
 
-    fn at_peak(&self, gradient_map: &GradientMap, next_position: &Position, ship: &Ship, game: &Game) -> bool {
+    fn at_peak(&self, gradient_map: &GradientMap, next_position: &Position, ship: &Ship, game: &Game, rng: &mut XorShiftRng) -> bool {
         let mut me_more = false;
         
         if game.ships.len() > 20 {
@@ -459,7 +482,7 @@ impl Navi {
                 me_more = true;
             }
         }
-        let next_possible_moves = self.get_possible_gather_move_vector(gradient_map, next_position, ship, true, me_more);
+        let next_possible_moves = self.get_possible_gather_move_vector(gradient_map, next_position, ship, true, me_more, rng);
         let next_turn_halite = self.next_turn_halite(&ship.position, next_position, ship, game);
         if next_possible_moves.len() < 2 && self.worth_to_home(next_turn_halite as usize, gradient_map, &next_position) {
             return true
@@ -467,10 +490,12 @@ impl Navi {
         false
     }
 
-    pub fn get_possible_gather_move_vector(&self, gradient_map: &GradientMap, position: &Position, ship: &Ship, for_next_turn: bool, me_more: bool) -> Vec<Direction> {
+    // the last move is the best one, an equally good later direction replaces it at random
+    pub fn get_possible_gather_move_vector(&self, gradient_map: &GradientMap, position: &Position, ship: &Ship, for_next_turn: bool, me_more: bool, rng: &mut XorShiftRng) -> Vec<Direction> {
         let origin_cell = gradient_map.at_position(position);
         let mut possible_moves: Vec<Direction> = vec![];
         let mut current_value = -500.0;
+        let mut ties = 1;
         if !origin_cell.my_occupy && !for_next_turn {
             current_value = origin_cell.value;
             possible_moves.push(Direction::Still);
@@ -495,13 +520,23 @@ impl Navi {
                 if potential_cell.value > current_value {
                     current_value = potential_cell.value;
                     possible_moves.push(direction);
+                    ties = 1;
+                } else if potential_cell.value == current_value && !possible_moves.is_empty() && rng::take_tie(rng, &mut ties) {
+                    possible_moves.pop();
+                    possible_moves.push(direction);
                 }
             } else {
-                if potential_value > current_value {
-                    if  (me_more && potential_cell.enemy_predicted_halite as usize > ship.halite * 2) || potential_cell.my_occupy == false {
-                        current_value = potential_value;
-                        possible_moves.push(direction);
-                        
+                if potential_value >= current_value {
+                    let safe = !potential_cell.capture_risk;
+                    if safe && ((me_more && potential_cell.enemy_predicted_halite as usize > ship.halite * 2) || potential_cell.my_occupy == false) {
+                        if potential_value > current_value {
+                            current_value = potential_value;
+                            possible_moves.push(direction);
+                            ties = 1;
+                        } else if !possible_moves.is_empty() && rng::take_tie(rng, &mut ties) {
+                            possible_moves.pop();
+                            possible_moves.push(direction);
+                        }
                     }
                 }
                 // if potential_value > current_value && potential_cell.my_occupy == false {
@@ -516,11 +551,11 @@ impl Navi {
     fn will_stall(&self, ship: &Ship, current_cell: &MapCell, next_cell: &MapCell) -> bool {
         let mut next_turn_ship_halite: isize  = 0;
         if current_cell.position.same_position(&next_cell.position) {
-            next_turn_ship_halite = ship.halite as isize + next_cell.halite as isize / 4;
+            next_turn_ship_halite = ship.halite as isize + next_cell.halite as isize / self.extract_ratio as isize;
         } else {
-            next_turn_ship_halite = ship.halite as isize - current_cell.halite as isize / 10;
+            next_turn_ship_halite = ship.halite as isize - current_cell.halite as isize / self.move_cost_ratio as isize;
         }
-        let will_stall = if next_turn_ship_halite  < next_cell.halite as isize / 10 {
+        let will_stall = if next_turn_ship_halite  < next_cell.halite as isize / self.move_cost_ratio as isize {
             true
         } else {
             false
@@ -540,33 +575,33 @@ impl Navi {
         nearest_dropoff: &Position,
     ) -> bool {
         // refactor so only compute disties once
-        if turn_number > &300 {
-            let turns_remaining = max_turns - turn_number;
-            let mut dis_x = 0;
-            let mut dis_y = 0;
-            if (self.width as i32 - ship_position.x).abs()
-                < (nearest_dropoff.x - ship_position.x).abs()
-            {
-                dis_x = self.width as i32 - ship_position.x + nearest_dropoff.x;
-            } else {
-                dis_x = (nearest_dropoff.x - ship_position.x).abs();
-            };
-            if (self.height as i32 - ship_position.y).abs()
-                < (nearest_dropoff.y - ship_position.y).abs()
-            {
-                dis_y = self.height as i32 - ship_position.y + nearest_dropoff.y;
-            } else {
-                dis_y = (nearest_dropoff.y - ship_position.y).abs();
-            };
+        // Dropoffs are never more than width + height moves away, so the distance check
+        // alone keeps this to the end of the game, whatever MAX_TURNS is.
+        let turns_remaining = max_turns.saturating_sub(*turn_number);
+        let mut dis_x = 0;
+        let mut dis_y = 0;
+        if (self.width as i32 - ship_position.x).abs()
+            < (nearest_dropoff.x - ship_position.x).abs()
+        {
+            dis_x = self.width as i32 - ship_position.x + nearest_dropoff.x;
+        } else {
+            dis_x = (nearest_dropoff.x - ship_position.x).abs();
+        };
+        if (self.height as i32 - ship_position.y).abs()
+            < (nearest_dropoff.y - ship_position.y).abs()
+        {
+            dis_y = self.height as i32 - ship_position.y + nearest_dropoff.y;
+        } else {
+            dis_y = (nearest_dropoff.y - ship_position.y).abs();
+        };
 
-            if turns_remaining < 15 {
-                return true
-            }
+        if turns_remaining < 15 {
+            return true
+        }
 
-            if dis_y + dis_x + 10 > turns_remaining as i32 {
-                return true
-            }
-        };
+        if dis_y + dis_x + 10 > turns_remaining as i32 {
+            return true
+        }
 
         false
     }
@@ -579,33 +614,32 @@ impl Navi {
         nearest_dropoff: &Position,
     ) -> bool {
         // refactor so only compute disties once
-        if turn_number > &300 {
-            let turns_remaining = max_turns - turn_number;
-            let mut dis_x = 0;
-            let mut dis_y = 0;
-            if (self.width as i32 - next_ship_position.x).abs()
-                < (nearest_dropoff.x - next_ship_position.x).abs()
-            {
-                dis_x = self.width as i32 - next_ship_position.x + nearest_dropoff.x;
-            } else {
-                dis_x = (nearest_dropoff.x - next_ship_position.x).abs();
-            };
-            if (self.height as i32 - next_ship_position.y).abs()
-                < (nearest_dropoff.y - next_ship_position.y).abs()
-            {
-                dis_y = self.height as i32 - next_ship_position.y + nearest_dropoff.y;
-            } else {
-                dis_y = (nearest_dropoff.y - next_ship_position.y).abs();
-            };
+        // Same window as end_game.
+        let turns_remaining = max_turns.saturating_sub(*turn_number);
+        let mut dis_x = 0;
+        let mut dis_y = 0;
+        if (self.width as i32 - next_ship_position.x).abs()
+            < (nearest_dropoff.x - next_ship_position.x).abs()
+        {
+            dis_x = self.width as i32 - next_ship_position.x + nearest_dropoff.x;
+        } else {
+            dis_x = (nearest_dropoff.x - next_ship_position.x).abs();
+        };
+        if (self.height as i32 - next_ship_position.y).abs()
+            < (nearest_dropoff.y - next_ship_position.y).abs()
+        {
+            dis_y = self.height as i32 - next_ship_position.y + nearest_dropoff.y;
+        } else {
+            dis_y = (nearest_dropoff.y - next_ship_position.y).abs();
+        };
 
-            if turns_remaining < 15 {
-                return true
-            }
+        if turns_remaining < 15 {
+            return true
+        }
 
-            if dis_y + dis_x + 5 > turns_remaining as i32 {
-                return true
-            }
-        };
+        if dis_y + dis_x + 5 > turns_remaining as i32 {
+            return true
+        }
 
         false
     }
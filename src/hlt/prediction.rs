@@ -0,0 +1,181 @@
+use hlt::command::Command;
+use hlt::forward_model::ForwardModel;
+use hlt::game::Game;
+use hlt::position::Position;
+use hlt::ShipId;
+use std::collections::HashSet;
+
+// What we expect the next frame to look like for our own player, given the commands we
+// just sent. Enemies are assumed to stay still, so a mismatch next to an enemy ship may
+// be their doing rather than a bug in our model; cells are only checked where no enemy
+// ship could have reached them.
+pub struct Prediction {
+    model: ForwardModel,
+    my_ship_ids: HashSet<ShipId>,
+    checked_cells: Vec<Position>,
+}
+
+impl Prediction {
+    pub fn new(game: &Game, commands: &[Command]) -> Prediction {
+        let mut model = ForwardModel::from_game(game);
+        let mut all_commands: Vec<Vec<Command>> = vec![Vec::new(); game.players.len()];
+        all_commands[game.my_id.0] = commands.to_vec();
+        model.step(&all_commands);
+
+        let my_ship_ids: HashSet<ShipId> = game.players[game.my_id.0].ship_ids.iter().cloned().collect();
+
+        let enemy_positions: Vec<Position> = game
+            .ships
+            .values()
+            .filter(|ship| ship.owner != game.my_id)
+            .map(|ship| ship.position)
+            .collect();
+        let checked_cells = game.players[game.my_id.0]
+            .ship_ids
+            .iter()
+            .map(|id| game.ships[id].position)
+            .filter(|position| {
+                enemy_positions
+                    .iter()
+                    .all(|enemy| game.map.calculate_distance(position, enemy) > 1)
+            })
+            .collect();
+
+        Prediction { model, my_ship_ids, checked_cells }
+    }
+
+    // Differences between the prediction and the frame that was actually read.
+    pub fn mismatches(&self, game: &Game) -> Vec<String> {
+        let mut mismatches: Vec<String> = Vec::new();
+        let me = &game.players[game.my_id.0];
+
+        if game.turn_number != self.model.turn_number {
+            mismatches.push(format!("turn {} but expected {}", game.turn_number, self.model.turn_number));
+        }
+
+        let expected_bank = self.model.banks[game.my_id.0];
+        if me.halite != expected_bank {
+            mismatches.push(format!("bank {} but expected {}", me.halite, expected_bank));
+        }
+
+        let mut ship_ids: Vec<&ShipId> = self.my_ship_ids.iter().collect();
+        ship_ids.sort_by_key(|id| id.0);
+        for ship_id in ship_ids {
+            match (self.model.ship(*ship_id), game.ships.get(ship_id)) {
+                (Some(expected), None) => mismatches.push(format!(
+                    "ship {} lost, expected at ({}, {}) with {}",
+                    ship_id.0, expected.position.x, expected.position.y, expected.halite
+                )),
+                (None, Some(actual)) => mismatches.push(format!(
+                    "ship {} expected gone but is at ({}, {})",
+                    ship_id.0, actual.position.x, actual.position.y
+                )),
+                (Some(expected), Some(actual)) => {
+                    if expected.owner != actual.owner {
+                        mismatches.push(format!(
+                            "ship {} belongs to player {} but expected player {}",
+                            ship_id.0, actual.owner.0, expected.owner.0
+                        ));
+                    }
+                    if expected.position != actual.position {
+                        mismatches.push(format!(
+                            "ship {} at ({}, {}) but expected ({}, {})",
+                            ship_id.0, actual.position.x, actual.position.y, expected.position.x, expected.position.y
+                        ));
+                    }
+                    if expected.halite != actual.halite {
+                        mismatches.push(format!(
+                            "ship {} carries {} but expected {}",
+                            ship_id.0, actual.halite, expected.halite
+                        ));
+                    }
+                }
+                (None, None) => (),
+            }
+        }
+
+        // New ship ids are handed out across all players, so spawns are only counted.
+        let expected_spawns = self
+            .model
+            .ships
+            .iter()
+            .filter(|ship| ship.owner == game.my_id && !self.my_ship_ids.contains(&ship.id))
+            .count();
+        let spawns = me.ship_ids.iter().filter(|id| !self.my_ship_ids.contains(id)).count();
+        if spawns != expected_spawns {
+            mismatches.push(format!("{} ships spawned but expected {}", spawns, expected_spawns));
+        }
+
+        for position in &self.checked_cells {
+            let expected = self.model.halite_at(position);
+            let actual = game.map.at_position(position).halite;
+            if expected != actual {
+                mismatches.push(format!(
+                    "cell ({}, {}) has {} but expected {}",
+                    position.x, position.y, actual, expected
+                ));
+            }
+        }
+
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::command::Command;
+    use hlt::direction::Direction;
+    use hlt::game::Game;
+    use hlt::prediction::Prediction;
+    use hlt::test_game::*;
+    use hlt::ShipId;
+
+    const YARDS: [(i32, i32); 2] = [(2, 2), (13, 13)];
+
+    fn move_ship(id: usize, direction: Direction) -> Command {
+        Command::Move(ShipId(id), direction)
+    }
+
+    // Our ship 0 leaves (5, 5) and two enemies crash into it, dropping 200 there; our
+    // ship 1 mines (10, 10) far from anyone.
+    fn crash_next_to_us() -> (Prediction, Game) {
+        let mut game = game(16, &YARDS);
+        add_ship(&mut game, 0, 0, 5, 5, 100);
+        add_ship(&mut game, 0, 1, 10, 10, 0);
+        add_ship(&mut game, 1, 2, 6, 5, 210);
+        add_ship(&mut game, 1, 3, 4, 5, 10);
+
+        let mine = vec![move_ship(0, Direction::North), move_ship(1, Direction::Still)];
+        let prediction = Prediction::new(&game, &mine);
+        step(&mut game, &[mine, vec![move_ship(2, Direction::West), move_ship(3, Direction::East)]]);
+        (prediction, game)
+    }
+
+    #[test]
+    fn enemy_moves_are_not_mismatches_next_to_enemies() {
+        let (prediction, game) = crash_next_to_us();
+        // The model kept the enemies still, so it expects (5, 5) untouched.
+        assert_eq!(game.map.at_position(&pos(5, 5)).halite, 300);
+        assert_eq!(game.map.at_position(&pos(10, 10)).halite, 75);
+        assert_eq!(prediction.mismatches(&game), Vec::<String>::new());
+    }
+
+    #[test]
+    fn cells_away_from_enemies_are_checked() {
+        let (prediction, mut game) = crash_next_to_us();
+        put_halite(&mut game, 10, 10, 50);
+        assert_eq!(prediction.mismatches(&game), vec!["cell (10, 10) has 50 but expected 75".to_string()]);
+    }
+
+    #[test]
+    fn an_enemy_ramming_us_is_reported() {
+        let mut game = game(16, &YARDS);
+        add_ship(&mut game, 0, 0, 5, 5, 0);
+        add_ship(&mut game, 1, 1, 6, 5, 100);
+
+        let mine = vec![move_ship(0, Direction::Still)];
+        let prediction = Prediction::new(&game, &mine);
+        step(&mut game, &[mine, vec![move_ship(1, Direction::West)]]);
+        assert_eq!(prediction.mismatches(&game), vec!["ship 0 lost, expected at (5, 5) with 25".to_string()]);
+    }
+}
@@ -0,0 +1,90 @@
+use hlt::command::Command;
+use hlt::game::Game;
+use hlt::game_map::GameMap;
+use hlt::PlayerId;
+use std::fmt::Write;
+
+// Server side of the text protocol that Input and Game::end_turn speak.
+
+pub fn init_message(game: &Game, player_id: PlayerId) -> String {
+    let mut message = String::new();
+    writeln!(message, "{}", game.constants.to_json()).unwrap();
+    writeln!(message, "{} {}", game.players.len(), player_id.0).unwrap();
+    for player in &game.players {
+        let position = player.shipyard.position;
+        writeln!(message, "{} {} {}", player.id.0, position.x, position.y).unwrap();
+    }
+
+    writeln!(message, "{} {}", game.map.width, game.map.height).unwrap();
+    for row in &game.map.cells {
+        let halite: Vec<String> = row.iter().map(|cell| cell.halite.to_string()).collect();
+        writeln!(message, "{}", halite.join(" ")).unwrap();
+    }
+    message
+}
+
+pub fn frame_message(game: &Game, previous_halite: &[Vec<usize>]) -> String {
+    let mut message = String::new();
+    writeln!(message, "{}", game.turn_number).unwrap();
+    for player in &game.players {
+        writeln!(
+            message,
+            "{} {} {} {}",
+            player.id.0,
+            player.ship_ids.len(),
+            player.dropoff_ids.len(),
+            player.halite
+        )
+        .unwrap();
+
+        for ship_id in &player.ship_ids {
+            let ship = &game.ships[ship_id];
+            writeln!(message, "{} {} {} {}", ship.id.0, ship.position.x, ship.position.y, ship.halite).unwrap();
+        }
+        for dropoff_id in &player.dropoff_ids {
+            let dropoff = &game.dropoffs[dropoff_id];
+            writeln!(message, "{} {} {}", dropoff.id.0, dropoff.position.x, dropoff.position.y).unwrap();
+        }
+    }
+
+    let mut updates: Vec<String> = Vec::new();
+    for (y, row) in game.map.cells.iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            if cell.halite != previous_halite[y][x] {
+                updates.push(format!("{} {} {}", x, y, cell.halite));
+            }
+        }
+    }
+    writeln!(message, "{}", updates.len()).unwrap();
+    for update in updates {
+        writeln!(message, "{}", update).unwrap();
+    }
+    message
+}
+
+pub fn halite_grid(map: &GameMap) -> Vec<Vec<usize>> {
+    map.cells.iter().map(|row| row.iter().map(|cell| cell.halite).collect()).collect()
+}
+
+// Splits a command line from a bot into its commands. Anything that doesn't parse is
+// returned as an error and left out of the commands.
+pub fn parse_command_line(line: &str) -> (Vec<Command>, Vec<String>) {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    let mut commands: Vec<Command> = Vec::new();
+    let mut errors: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let length = match tokens[i] {
+            "c" => 2,
+            "m" => 3,
+            _ => 1,
+        };
+        let end = (i + length).min(tokens.len());
+        match Command::parse(&tokens[i..end].join(" ")) {
+            Ok(command) => commands.push(command),
+            Err(error) => errors.push(error),
+        }
+        i = end;
+    }
+    (commands, errors)
+}
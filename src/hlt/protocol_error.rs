@@ -0,0 +1,52 @@
+use hlt::log::Log;
+use std::fmt;
+use std::process::exit;
+
+// Why the text from the server could not be read into a Game.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProtocolError {
+    // End of input, which is also how every game ends.
+    Closed,
+    Read(String),
+    MissingToken { line: String },
+    BadToken { token: String, expected: &'static str },
+    OutOfRange { what: &'static str, value: i64 },
+    BadConstants(String),
+    BadConstant { key: String, value: String },
+}
+
+impl ProtocolError {
+    // For bot binaries that have nothing better to do than stop: a closed input ends the
+    // game normally, anything else is logged as a failure.
+    pub fn exit(&self) -> ! {
+        if *self == ProtocolError::Closed {
+            Log::log("Input connection from server closed. Exiting...");
+            Log::flush();
+            exit(0);
+        }
+        Log::panic(&format!("Error: protocol: {}", self))
+    }
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProtocolError::Closed => write!(f, "input connection from server closed"),
+            ProtocolError::Read(reason) => write!(f, "can't read from server: {}", reason),
+            ProtocolError::MissingToken { line } => write!(f, "expected more tokens in '{}'", line),
+            ProtocolError::BadToken { token, expected } => write!(f, "can't parse '{}' as {}", token, expected),
+            ProtocolError::OutOfRange { what, value } => write!(f, "{} {} is out of range", what, value),
+            ProtocolError::BadConstants(reason) => write!(f, "can't read the constants: {}", reason),
+            ProtocolError::BadConstant { key, value } => {
+                write!(f, "for {} got '{}' from server and failed to parse that", key, value)
+            }
+        }
+    }
+}
+
+// Lets the tools that report errors as strings use `?` on parsing.
+impl From<ProtocolError> for String {
+    fn from(error: ProtocolError) -> String {
+        error.to_string()
+    }
+}
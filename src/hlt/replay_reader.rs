@@ -0,0 +1,284 @@
+use hlt::command::Command;
+use hlt::constants::Constants;
+use hlt::direction::Direction;
+use hlt::dropoff::Dropoff;
+use hlt::game::Game;
+use hlt::game_map::GameMap;
+use hlt::json::Json;
+use hlt::map_cell::MapCell;
+use hlt::map_cell::Structure;
+use hlt::player::Player;
+use hlt::position::Position;
+use hlt::ship::Ship;
+use hlt::shipyard::Shipyard;
+use hlt::DropoffId;
+use hlt::PlayerId;
+use hlt::ShipId;
+use std::fs;
+
+pub struct ReplayFrame {
+    pub game: Game,
+    pub commands: Vec<Vec<Command>>,
+}
+
+pub struct Replay {
+    pub names: Vec<String>,
+    pub seed: u64,
+    pub frames: Vec<ReplayFrame>,
+    pub final_halite: Vec<usize>,
+}
+
+// Reads uncompressed replay JSON into one Game per frame. Frame i holds the state the
+// bots saw on turn i and the commands they sent; the cell changes and construct events
+// listed in a frame take effect from the following frame on. Every Game is built from
+// the point of view of player 0, set `my_id` to look at it as someone else.
+impl Replay {
+    pub fn load(path: &str) -> Result<Replay, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("can't read replay {}: {}", path, e))?;
+        Replay::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Replay, String> {
+        let root = Json::parse(text)?;
+        let constants_json = field(&root, "GAME_CONSTANTS")?;
+        let constants = Constants::from_json(constants_json)?;
+        let seed = root.get("map_generator_seed").and_then(Json::as_i64).unwrap_or(0) as u64;
+
+        let mut players: Vec<Player> = Vec::new();
+        let mut names: Vec<String> = Vec::new();
+        for player_json in array(field(&root, "players")?)? {
+            let id = PlayerId(usize_field(player_json, "player_id")?);
+            let position = location(field(player_json, "factory_location")?)?;
+            let halite = player_json.get("energy").and_then(Json::as_usize).unwrap_or(0);
+
+            names.push(player_json.get("name").and_then(Json::as_str).unwrap_or("").to_string());
+            players.push(Player {
+                id,
+                shipyard: Shipyard { owner: id, position },
+                halite,
+                ship_ids: Vec::new(),
+                dropoff_ids: Vec::new(),
+            });
+        }
+        players.sort_by_key(|player| player.id.0);
+        if players.iter().enumerate().any(|(i, player)| player.id.0 != i) {
+            return Err("player ids must run from 0 to number_of_players - 1".to_string());
+        }
+
+        let production_map = field(&root, "production_map")?;
+        let width = usize_field(production_map, "width")?;
+        let height = usize_field(production_map, "height")?;
+        let mut halite: Vec<Vec<usize>> = Vec::with_capacity(height);
+        for row in array(field(production_map, "grid")?)? {
+            let row = array(row)?;
+            halite.push(row.iter().map(|cell| usize_field(cell, "energy")).collect::<Result<_, _>>()?);
+        }
+        if halite.len() != height || halite.iter().any(|row| row.len() != width) {
+            return Err("production_map grid does not match its width and height".to_string());
+        }
+        for player in &players {
+            check_location(&player.shipyard.position, width, height)?;
+        }
+
+        // Each frame's map is the one before with that turn's cell changes applied, so the
+        // totals and per-turn changes are the same as a bot following the game would see.
+        let mut map = Replay::build_map(width, height, &halite);
+        let mut dropoffs: Vec<Dropoff> = Vec::new();
+        let mut frames: Vec<ReplayFrame> = Vec::new();
+        for (turn_number, frame_json) in array(field(&root, "full_frames")?)?.iter().enumerate() {
+            let mut frame_players = players.clone();
+            for dropoff in &dropoffs {
+                frame_players[dropoff.owner.0].dropoff_ids.push(dropoff.id);
+            }
+            if let Some(energy) = frame_json.get("energy") {
+                for player in &mut frame_players {
+                    if let Some(bank) = energy.get(&player.id.0.to_string()).and_then(Json::as_usize) {
+                        player.halite = bank;
+                    }
+                }
+            }
+
+            let mut game = Game::construct(
+                constants.clone(),
+                PlayerId(0),
+                frame_players,
+                map.clone(),
+            );
+            game.turn_number = turn_number;
+
+            for dropoff in &dropoffs {
+                game.map.at_position_mut(&dropoff.position).structure = Structure::Dropoff(dropoff.id);
+                game.dropoffs.insert(dropoff.id, dropoff.clone());
+            }
+            Replay::read_entities(frame_json, &mut game)?;
+            let commands = Replay::read_commands(frame_json, game.players.len())?;
+
+            map.begin_turn();
+            if let Some(cells) = frame_json.get("cells").and_then(Json::as_array) {
+                for cell in cells {
+                    let x = usize_field(cell, "x")?;
+                    let y = usize_field(cell, "y")?;
+                    check_location(&Position { x: x as i32, y: y as i32 }, width, height)?;
+                    map.set_halite(&Position { x: x as i32, y: y as i32 }, usize_field(cell, "production")?);
+                }
+            }
+            if let Some(events) = frame_json.get("events").and_then(Json::as_array) {
+                for event in events {
+                    if event.get("type").and_then(Json::as_str) == Some("construct") {
+                        let owner = usize_field(event, "owner_id")?;
+                        if owner >= players.len() {
+                            return Err(format!("construct event for unknown player {}", owner));
+                        }
+                        let position = location(field(event, "location")?)?;
+                        check_location(&position, width, height)?;
+                        dropoffs.push(Dropoff { owner: PlayerId(owner), id: DropoffId(usize_field(event, "id")?), position });
+                    }
+                }
+            }
+
+            frames.push(ReplayFrame { game, commands });
+        }
+
+        let mut final_halite: Vec<usize> = match frames.last() {
+            Some(frame) => frame.game.players.iter().map(|player| player.halite).collect(),
+            None => vec![0; players.len()],
+        };
+        let statistics = root.get("game_statistics").and_then(|x| x.get("player_statistics")).and_then(Json::as_array);
+        for player_statistics in statistics.map_or(&[][..], |x| &x[..]) {
+            let player_id = player_statistics.get("player_id").and_then(Json::as_usize);
+            let halite = player_statistics.get("final_production").and_then(Json::as_usize);
+            if let (Some(player_id), Some(halite)) = (player_id, halite) {
+                if player_id < final_halite.len() {
+                    final_halite[player_id] = halite;
+                }
+            }
+        }
+
+        Ok(Replay { names, seed, frames, final_halite })
+    }
+
+    fn build_map(width: usize, height: usize, halite: &[Vec<usize>]) -> GameMap {
+        let mut cells: Vec<Vec<MapCell>> = Vec::with_capacity(height);
+        for (y, row) in halite.iter().enumerate() {
+            cells.push(
+                row.iter()
+                    .enumerate()
+                    .map(|(x, halite)| {
+                        let position = Position { x: x as i32, y: y as i32 };
+                        MapCell { position, halite: *halite, structure: Structure::None }
+                    })
+                    .collect(),
+            );
+        }
+        GameMap::new(width, height, cells)
+    }
+
+    fn read_entities(frame_json: &Json, game: &mut Game) -> Result<(), String> {
+        let entities = match frame_json.get("entities").and_then(Json::as_object) {
+            Some(entities) => entities,
+            None => return Ok(()),
+        };
+
+        for (player_key, ships) in entities {
+            let owner = PlayerId(player_key.parse().map_err(|_| format!("bad player id '{}'", player_key))?);
+            if owner.0 >= game.players.len() {
+                return Err(format!("entities for unknown player {}", owner.0));
+            }
+
+            let ships = ships.as_object().ok_or_else(|| "entities must be objects".to_string())?;
+            let mut ship_ids: Vec<ShipId> = Vec::new();
+            for (ship_key, ship_json) in ships {
+                let id = ShipId(ship_key.parse().map_err(|_| format!("bad ship id '{}'", ship_key))?);
+                let position = Position {
+                    x: usize_field(ship_json, "x")? as i32,
+                    y: usize_field(ship_json, "y")? as i32,
+                };
+                check_location(&position, game.map.width, game.map.height)?;
+                let halite = usize_field(ship_json, "energy")?;
+
+                ship_ids.push(id);
+                game.ships.insert(id, Ship::new(owner, id, position, halite, game.constants.max_halite));
+            }
+            ship_ids.sort_by_key(|id| id.0);
+            game.players[owner.0].ship_ids = ship_ids;
+        }
+        Ok(())
+    }
+
+    fn read_commands(frame_json: &Json, num_players: usize) -> Result<Vec<Vec<Command>>, String> {
+        let mut commands: Vec<Vec<Command>> = vec![Vec::new(); num_players];
+        let moves = match frame_json.get("moves").and_then(Json::as_object) {
+            Some(moves) => moves,
+            None => return Ok(commands),
+        };
+
+        for (player_key, player_moves) in moves {
+            let player_index: usize = player_key.parse().map_err(|_| format!("bad player id '{}'", player_key))?;
+            if player_index >= num_players {
+                return Err(format!("moves for unknown player {}", player_index));
+            }
+
+            for move_json in array(player_moves)? {
+                let command = match move_json.get("type").and_then(Json::as_str) {
+                    Some("g") => Command::spawn_ship(),
+                    Some("c") => Command::transform_ship_into_dropoff_site(ShipId(usize_field(move_json, "id")?)),
+                    Some("m") => {
+                        let direction = move_json
+                            .get("direction")
+                            .and_then(Json::as_str)
+                            .and_then(|x| x.chars().next())
+                            .and_then(Direction::from_char_encoding)
+                            .ok_or_else(|| "move without a valid direction".to_string())?;
+                        Command::move_ship(ShipId(usize_field(move_json, "id")?), direction)
+                    }
+                    _ => return Err(format!("unknown move {}", move_json)),
+                };
+                commands[player_index].push(command);
+            }
+        }
+        Ok(commands)
+    }
+}
+
+fn field<'a>(json: &'a Json, key: &str) -> Result<&'a Json, String> {
+    json.get(key).ok_or_else(|| format!("replay is missing '{}'", key))
+}
+
+fn usize_field(json: &Json, key: &str) -> Result<usize, String> {
+    field(json, key)?.as_usize().ok_or_else(|| format!("'{}' is not a non-negative integer", key))
+}
+
+fn array(json: &Json) -> Result<&Vec<Json>, String> {
+    json.as_array().ok_or_else(|| "expected an array".to_string())
+}
+
+fn location(json: &Json) -> Result<Position, String> {
+    Ok(Position { x: usize_field(json, "x")? as i32, y: usize_field(json, "y")? as i32 })
+}
+
+fn check_location(position: &Position, width: usize, height: usize) -> Result<(), String> {
+    if position.x as usize >= width || position.y as usize >= height {
+        return Err(format!("({}, {}) is off the map", position.x, position.y));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::replay_reader::Replay;
+
+    const REPLAY: &str = include_str!("../../tests/data/replay-2p-32x32.json");
+
+    #[test]
+    fn construct_for_an_unknown_player_is_an_error() {
+        let text = REPLAY.replace(r#""owner_id":1,"type":"construct""#, r#""owner_id":7,"type":"construct""#);
+        assert!(Replay::parse(&text).is_err());
+    }
+
+    #[test]
+    fn ship_off_the_map_is_an_error() {
+        let text = REPLAY.replace(r#""is_inspired":false,"x":24,"#, r#""is_inspired":false,"x":32,"#);
+        assert_ne!(text, REPLAY);
+        assert!(Replay::parse(&text).is_err());
+    }
+}
@@ -0,0 +1,414 @@
+use hlt::command::Command;
+use hlt::engine::Engine;
+use hlt::game::Game;
+use hlt::json::Json;
+use hlt::position::Position;
+use hlt::DropoffId;
+use hlt::PlayerId;
+use hlt::ShipId;
+use std::collections::BTreeMap;
+use std::io;
+use std::io::Write;
+
+pub const REPLAY_FILE_VERSION: i64 = 3;
+pub const ENGINE_VERSION: &str = "1.1.6";
+
+struct ShipState {
+    owner: PlayerId,
+    position: Position,
+    halite: usize,
+}
+
+// The parts of a frame needed to diff it against the next one.
+struct FrameState {
+    ships: BTreeMap<usize, ShipState>,
+    dropoffs: BTreeMap<usize, (PlayerId, Position)>,
+    banks: Vec<usize>,
+    halite: Vec<Vec<usize>>,
+    commands: Vec<Vec<Command>>,
+}
+
+impl FrameState {
+    fn capture(game: &Game, commands: &[Vec<Command>]) -> FrameState {
+        let ships = game
+            .ships
+            .values()
+            .map(|ship| (ship.id.0, ShipState { owner: ship.owner, position: ship.position, halite: ship.halite }))
+            .collect();
+        let dropoffs = game
+            .dropoffs
+            .values()
+            .map(|dropoff| (dropoff.id.0, (dropoff.owner, dropoff.position)))
+            .collect();
+        let halite = game.map.cells.iter().map(|row| row.iter().map(|cell| cell.halite).collect()).collect();
+
+        FrameState {
+            ships,
+            dropoffs,
+            banks: game.players.iter().map(|player| player.halite).collect(),
+            halite,
+            commands: commands.to_vec(),
+        }
+    }
+}
+
+// Writes Halite III replay JSON from a sequence of frames. Call record_turn with the state
+// every bot saw at the start of a turn together with the commands they answered with,
+// then finish with the state after the last turn. Events, changed cells and deposits of a
+// frame are derived by diffing it against the frame that follows.
+pub struct ReplayWriter {
+    constants: Json,
+    seed: u64,
+    names: Vec<String>,
+    shipyards: Vec<Position>,
+    initial_banks: Vec<usize>,
+    production_map: Json,
+    frames: Vec<Json>,
+    pending: Option<(Json, FrameState)>,
+    deposited: Vec<usize>,
+    // Banks after the last turn, set by finish.
+    final_banks: Option<Vec<usize>>,
+    ship_cost: usize,
+    dropoff_cost: usize,
+    move_cost_ratio: usize,
+}
+
+impl ReplayWriter {
+    pub fn new(game: &Game, names: &[String], seed: u64) -> ReplayWriter {
+        let grid = game
+            .map
+            .cells
+            .iter()
+            .map(|row| {
+                Json::Array(row.iter().map(|cell| Json::object().with("energy", Json::Int(cell.halite as i64))).collect())
+            })
+            .collect();
+        let production_map = Json::object()
+            .with("grid", Json::Array(grid))
+            .with("height", Json::Int(game.map.height as i64))
+            .with("map_generator", Json::Str("Fractal Value Noise Tile".to_string()))
+            .with("width", Json::Int(game.map.width as i64));
+
+        let mut writer = ReplayWriter {
+            constants: game.constants.to_json(),
+            seed,
+            names: names.to_vec(),
+            shipyards: game.players.iter().map(|player| player.shipyard.position).collect(),
+            initial_banks: game.players.iter().map(|player| player.halite).collect(),
+            production_map,
+            frames: Vec::new(),
+            pending: None,
+            deposited: vec![0; game.players.len()],
+            final_banks: None,
+            ship_cost: game.constants.ship_cost,
+            dropoff_cost: game.constants.dropoff_cost,
+            move_cost_ratio: game.constants.move_cost_ratio,
+        };
+        writer.record_turn(game, &vec![Vec::new(); game.players.len()]);
+        // The official engine writes the frame before the first turn without any
+        // per-player entities or moves.
+        if let Some((frame, _)) = writer.pending.take() {
+            let frame = frame.with("entities", Json::object()).with("moves", Json::object());
+            writer.pending = Some((frame, FrameState::capture(game, &[])));
+        }
+        writer
+    }
+
+    pub fn record_turn(&mut self, game: &Game, commands: &[Vec<Command>]) {
+        self.close_pending(game);
+
+        let inspired = Engine::inspired_ships(game);
+        let mut entities = BTreeMap::new();
+        let mut energy = BTreeMap::new();
+        let mut moves = BTreeMap::new();
+
+        for player in &game.players {
+            let mut ships = BTreeMap::new();
+            for ship_id in &player.ship_ids {
+                let ship = &game.ships[ship_id];
+                let entity = Json::object()
+                    .with("energy", Json::Int(ship.halite as i64))
+                    .with("is_inspired", Json::Bool(inspired.contains(ship_id)))
+                    .with("x", Json::Int(ship.position.x as i64))
+                    .with("y", Json::Int(ship.position.y as i64));
+                ships.insert(ship_id.0.to_string(), entity);
+            }
+            entities.insert(player.id.0.to_string(), Json::Object(ships));
+            energy.insert(player.id.0.to_string(), Json::Int(player.halite as i64));
+
+            let player_commands = commands.get(player.id.0).map_or(&[][..], |x| &x[..]);
+            let player_moves = player_commands.iter().map(ReplayWriter::move_json).collect();
+            moves.insert(player.id.0.to_string(), Json::Array(player_moves));
+        }
+
+        let frame = Json::object()
+            .with("energy", Json::Object(energy))
+            .with("entities", Json::Object(entities))
+            .with("moves", Json::Object(moves));
+        self.pending = Some((frame, FrameState::capture(game, commands)));
+    }
+
+    pub fn finish(&mut self, game: &Game) {
+        self.close_pending(game);
+        self.final_banks = Some(game.players.iter().map(|player| player.halite).collect());
+    }
+
+    pub fn to_json(&self) -> Json {
+        let players = self
+            .shipyards
+            .iter()
+            .enumerate()
+            .map(|(i, position)| {
+                let name = self.names.get(i).cloned().unwrap_or_else(|| format!("player {}", i));
+                Json::object()
+                    .with("energy", Json::Int(self.initial_banks[i] as i64))
+                    .with("entities", Json::Array(Vec::new()))
+                    .with("factory_location", ReplayWriter::location_json(position))
+                    .with("name", Json::Str(name))
+                    .with("player_id", Json::Int(i as i64))
+            })
+            .collect();
+
+        Json::object()
+            .with("ENGINE_VERSION", Json::Str(ENGINE_VERSION.to_string()))
+            .with("GAME_CONSTANTS", self.constants.clone())
+            .with("REPLAY_FILE_VERSION", Json::Int(REPLAY_FILE_VERSION))
+            .with("full_frames", Json::Array(self.frames.clone()))
+            .with("game_statistics", self.statistics_json())
+            .with("map_generator_seed", Json::Int(self.seed as i64))
+            .with("number_of_players", Json::Int(self.shipyards.len() as i64))
+            .with("players", Json::Array(players))
+            .with("production_map", self.production_map.clone())
+    }
+
+    pub fn write(&self, writer: &mut dyn Write) -> io::Result<()> {
+        let text = self.to_json().to_text().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        writer.write_all(text.as_bytes())
+    }
+
+    fn close_pending(&mut self, next: &Game) {
+        let (frame, state) = match self.pending.take() {
+            Some(pending) => pending,
+            None => return,
+        };
+
+        let mut events = Vec::new();
+        let mut spent: Vec<usize> = vec![0; state.banks.len()];
+        let mut constructing: Vec<usize> = Vec::new();
+
+        let mut spawned: Vec<&ShipId> = next.ships.keys().filter(|id| !state.ships.contains_key(&id.0)).collect();
+        spawned.sort_by_key(|id| id.0);
+        for ship_id in spawned {
+            let ship = &next.ships[ship_id];
+            spent[ship.owner.0] += self.ship_cost;
+            events.push(
+                Json::object()
+                    .with("energy", Json::Int(ship.halite as i64))
+                    .with("id", Json::Int(ship_id.0 as i64))
+                    .with("location", ReplayWriter::location_json(&ship.position))
+                    .with("owner_id", Json::Int(ship.owner.0 as i64))
+                    .with("type", Json::Str("spawn".to_string())),
+            );
+        }
+
+        let mut constructed: Vec<&DropoffId> = next.dropoffs.keys().filter(|id| !state.dropoffs.contains_key(&id.0)).collect();
+        constructed.sort_by_key(|id| id.0);
+        for dropoff_id in constructed {
+            let dropoff = &next.dropoffs[dropoff_id];
+            let builder = state.ships.iter().find(|(id, ship)| {
+                ship.owner == dropoff.owner && ship.position == dropoff.position && !next.ships.contains_key(&ShipId(**id))
+            });
+            if let Some((id, ship)) = builder {
+                constructing.push(*id);
+                let cell_halite = state.halite[ship.position.y as usize][ship.position.x as usize];
+                spent[dropoff.owner.0] += self.dropoff_cost.saturating_sub(ship.halite + cell_halite);
+            }
+            events.push(
+                Json::object()
+                    .with("id", Json::Int(dropoff_id.0 as i64))
+                    .with("location", ReplayWriter::location_json(&dropoff.position))
+                    .with("owner_id", Json::Int(dropoff.owner.0 as i64))
+                    .with("type", Json::Str("construct".to_string())),
+            );
+        }
+
+        let mut wrecks: BTreeMap<(i32, i32), Vec<Json>> = BTreeMap::new();
+        for (id, ship) in &state.ships {
+            if next.ships.contains_key(&ShipId(*id)) || constructing.contains(id) {
+                continue;
+            }
+            let position = self.wreck_position(&state, *id, ship, next);
+            wrecks.entry((position.y, position.x)).or_default().push(Json::Int(*id as i64));
+        }
+        for ((y, x), ship_ids) in wrecks {
+            events.push(
+                Json::object()
+                    .with("location", ReplayWriter::location_json(&Position { x, y }))
+                    .with("ships", Json::Array(ship_ids))
+                    .with("type", Json::Str("shipwreck".to_string())),
+            );
+        }
+
+        let mut cells = Vec::new();
+        for (y, row) in next.map.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if cell.halite != state.halite[y][x] {
+                    cells.push(
+                        Json::object()
+                            .with("production", Json::Int(cell.halite as i64))
+                            .with("x", Json::Int(x as i64))
+                            .with("y", Json::Int(y as i64)),
+                    );
+                }
+            }
+        }
+
+        let mut deposited = BTreeMap::new();
+        for (i, player) in next.players.iter().enumerate().take(state.banks.len()) {
+            let income = (player.halite + spent[i]) as i64 - state.banks[i] as i64;
+            self.deposited[i] += income.max(0) as usize;
+            deposited.insert(i.to_string(), Json::Int(self.deposited[i] as i64));
+        }
+
+        let frame = frame
+            .with("cells", Json::Array(cells))
+            .with("deposited", Json::Object(deposited))
+            .with("events", Json::Array(events));
+        self.frames.push(frame);
+    }
+
+    // A lost ship wrecked where its move command would have taken it.
+    fn wreck_position(&self, state: &FrameState, id: usize, ship: &ShipState, next: &Game) -> Position {
+        let commands = match state.commands.get(ship.owner.0) {
+            Some(commands) => commands,
+            None => return ship.position,
+        };
+
+        for command in commands {
+            if let Command::Move(ship_id, direction) = *command {
+                if ship_id.0 != id {
+                    continue;
+                }
+                let cell_halite = state.halite[ship.position.y as usize][ship.position.x as usize];
+                if ship.halite >= Engine::move_cost(cell_halite, self.move_cost_ratio) {
+                    return next.map.normalize(&ship.position.directional_offset(direction));
+                }
+            }
+        }
+        ship.position
+    }
+
+    fn statistics_json(&self) -> Json {
+        let final_banks: Vec<i64> = match (&self.final_banks, self.frames.last()) {
+            (Some(banks), _) => banks.iter().map(|x| *x as i64).collect(),
+            (None, Some(frame)) => (0..self.shipyards.len())
+                .map(|i| {
+                    frame.get("energy").and_then(|x| x.get(&i.to_string())).and_then(Json::as_i64).unwrap_or(0)
+                })
+                .collect(),
+            (None, None) => vec![0; self.shipyards.len()],
+        };
+
+        let mut ranking: Vec<usize> = (0..self.shipyards.len()).collect();
+        ranking.sort_by(|a, b| final_banks[*b].cmp(&final_banks[*a]));
+
+        let statistics = (0..self.shipyards.len())
+            .map(|i| {
+                let rank = ranking.iter().position(|x| *x == i).unwrap() + 1;
+                Json::object()
+                    .with("final_production", Json::Int(final_banks[i]))
+                    .with("player_id", Json::Int(i as i64))
+                    .with("rank", Json::Int(rank as i64))
+                    .with("total_production", Json::Int(self.deposited[i] as i64))
+            })
+            .collect();
+
+        Json::object()
+            .with("number_turns", Json::Int(self.frames.len() as i64))
+            .with("player_statistics", Json::Array(statistics))
+    }
+
+    fn move_json(command: &Command) -> Json {
+        match *command {
+            Command::Spawn => Json::object().with("type", Json::Str("g".to_string())),
+            Command::Construct(ship_id) => Json::object()
+                .with("id", Json::Int(ship_id.0 as i64))
+                .with("type", Json::Str("c".to_string())),
+            Command::Move(ship_id, direction) => Json::object()
+                .with("direction", Json::Str(direction.get_char_encoding().to_string()))
+                .with("id", Json::Int(ship_id.0 as i64))
+                .with("type", Json::Str("m".to_string())),
+        }
+    }
+
+    fn location_json(position: &Position) -> Json {
+        Json::object()
+            .with("x", Json::Int(position.x as i64))
+            .with("y", Json::Int(position.y as i64))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use hlt::engine::Engine;
+    use hlt::json::Json;
+    use hlt::replay_reader::Replay;
+    use hlt::replay_writer::ReplayWriter;
+    use hlt::snapshot::Snapshot;
+
+    // The first five turns of a 2 player game on a 32x32 map with the official constants,
+    // laid out like the replays of the 1.1.6 engine: spawns, moves, mining, a construct
+    // and a collision on a shipyard. No replay from the official engine was at hand, so
+    // the frames were worked out by hand from the rules rather than recorded.
+    const REPLAY: &str = include_str!("../../tests/data/replay-2p-32x32.json");
+
+    // Arrays whose order the engine doesn't define.
+    fn is_unordered(path: &str) -> bool {
+        path.ends_with(".events") || path.ends_with(".cells") || path.contains(".moves.")
+    }
+
+    fn assert_same(path: &str, written: &Json, expected: &Json) {
+        match (written, expected) {
+            (Json::Object(written), Json::Object(expected)) => {
+                let written_keys: Vec<&String> = written.keys().collect();
+                let expected_keys: Vec<&String> = expected.keys().collect();
+                assert_eq!(written_keys, expected_keys, "keys of {}", path);
+                for (key, value) in written {
+                    assert_same(&format!("{}.{}", path, key), value, &expected[key]);
+                }
+            }
+            (Json::Array(written), Json::Array(expected)) if is_unordered(path) => {
+                let mut written: Vec<String> = written.iter().map(|x| x.to_string()).collect();
+                let mut expected: Vec<String> = expected.iter().map(|x| x.to_string()).collect();
+                written.sort();
+                expected.sort();
+                assert_eq!(written, expected, "{}", path);
+            }
+            (Json::Array(written), Json::Array(expected)) => {
+                assert_eq!(written.len(), expected.len(), "length of {}", path);
+                for (i, (written, expected)) in written.iter().zip(expected).enumerate() {
+                    assert_same(&format!("{}[{}]", path, i), written, expected);
+                }
+            }
+            _ => assert_eq!(written, expected, "{}", path),
+        }
+    }
+
+    #[test]
+    fn regenerated_replay_matches_the_recorded_one() {
+        let replay = Replay::parse(REPLAY).unwrap();
+        let snapshot = Snapshot::from_replay(&replay, 0).unwrap();
+        let mut game = snapshot.game;
+        let mut engine = Engine::resume(snapshot.next_ship_id, snapshot.next_dropoff_id);
+
+        let mut writer = ReplayWriter::new(&game, &replay.names, replay.seed);
+        for frame in &replay.frames[1..] {
+            writer.record_turn(&game, &frame.commands);
+            engine.resolve_turn(&mut game, &frame.commands);
+        }
+        writer.finish(&game);
+
+        assert_same("replay", &writer.to_json(), &Json::parse(REPLAY).unwrap());
+    }
+}
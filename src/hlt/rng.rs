@@ -0,0 +1,62 @@
+use rand::prng::XorShiftRng;
+use rand::Rng;
+use rand::SeedableRng;
+
+// Expands a u64 seed into the 16 bytes XorShiftRng wants with splitmix64, so nearby
+// seeds still give unrelated streams.
+pub fn seeded(seed: u64) -> XorShiftRng {
+    let mut state = seed;
+    let mut bytes = [0u8; 16];
+    for chunk in bytes.chunks_mut(8) {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        for (i, byte) in chunk.iter_mut().enumerate() {
+            *byte = (z >> (i * 8)) as u8;
+        }
+    }
+    XorShiftRng::from_seed(bytes)
+}
+
+// For picking uniformly among equally good candidates seen one at a time: call it on
+// every tie with the number of candidates tied so far (starting at 1 for the current
+// best) and take the new one when it returns true.
+pub fn take_tie(rng: &mut XorShiftRng, ties: &mut usize) -> bool {
+    *ties += 1;
+    rng.gen_range(0, *ties) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::arena::Arena;
+    use hlt::baseline::GreedyMiner;
+    use hlt::bot::Bot;
+    use hlt::command::Command;
+    use hlt::strategy::Strategy;
+    use hlt::PlayerId;
+
+    // Everything the bot sends over a short game against a fixed opponent.
+    fn commands_with_seed(seed: u64) -> Vec<String> {
+        let mut arena = Arena::generate(11, 32, 32, 2).unwrap();
+        let mut bot = Bot::new(&arena.view(PlayerId(0)), seed);
+        let mut opponent = GreedyMiner::new(&arena.view(PlayerId(1)));
+
+        let mut sent = Vec::new();
+        while arena.game.turn_number <= 60 {
+            let commands: Vec<Command> = bot.play_turn(&arena.view(PlayerId(0)));
+            sent.push(commands.iter().map(|command| command.to_string()).collect::<Vec<String>>().join(" "));
+            let opponent_commands = opponent.play_turn(&arena.view(PlayerId(1)));
+            arena.resolve(&[commands, opponent_commands]);
+        }
+        sent
+    }
+
+    #[test]
+    fn the_seed_pins_down_every_command() {
+        let first = commands_with_seed(7);
+        assert_eq!(first, commands_with_seed(7));
+        assert_ne!(first, commands_with_seed(8));
+    }
+}
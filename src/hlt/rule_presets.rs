@@ -0,0 +1,182 @@
+use hlt::json::Json;
+use std::fs;
+
+// Named sets of constant overrides for trying our heuristics under other rules. Values
+// are written the way the server writes them, so an integer key stays an integer.
+pub const PRESETS: [(&str, &str); 9] = [
+    ("no-inspiration", r#"{"INSPIRATION_ENABLED":false}"#),
+    ("rich-mining", r#"{"EXTRACT_RATIO":2,"INSPIRED_EXTRACT_RATIO":2}"#),
+    ("slow-mining", r#"{"EXTRACT_RATIO":8,"INSPIRED_EXTRACT_RATIO":8}"#),
+    ("cheap-moves", r#"{"INSPIRED_MOVE_COST_RATIO":20,"MOVE_COST_RATIO":20}"#),
+    ("expensive-moves", r#"{"INSPIRED_MOVE_COST_RATIO":5,"MOVE_COST_RATIO":5}"#),
+    ("cheap-ships", r#"{"NEW_ENTITY_ENERGY_COST":500}"#),
+    ("cheap-dropoffs", r#"{"DROPOFF_COST":2000}"#),
+    ("short-game", r#"{"MAX_TURNS":200}"#),
+    ("capture", r#"{"CAPTURE_ENABLED":true}"#),
+];
+
+pub fn preset(name: &str) -> Option<Json> {
+    PRESETS
+        .iter()
+        .find(|(preset_name, _)| *preset_name == name)
+        .map(|(_, overrides)| Json::parse(overrides).unwrap())
+}
+
+pub fn load(path: &str) -> Result<Json, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("can't read {}: {}", path, e))?;
+    let overrides = Json::parse(&text).map_err(|e| format!("{}: {}", path, e))?;
+    match overrides {
+        Json::Object(_) => Ok(overrides),
+        _ => Err(format!("{}: overrides must be a JSON object", path)),
+    }
+}
+
+// Parses a single KEY=VALUE override, VALUE being JSON (3, 2.5, true).
+pub fn parse_assignment(assignment: &str) -> Result<Json, String> {
+    let mut parts = assignment.splitn(2, '=');
+    let key = parts.next().unwrap_or("");
+    let value = match parts.next() {
+        Some(value) if !key.is_empty() => Json::parse(value).map_err(|e| format!("{}: {}", assignment, e))?,
+        _ => return Err(format!("expected KEY=VALUE, got '{}'", assignment)),
+    };
+    Ok(Json::object().with(key, value))
+}
+
+// Later overrides win. Every key must already be in the constants block and keep its
+// type, except that integers are accepted for float keys and written as floats.
+pub fn apply(constants: &Json, overrides: &[Json]) -> Result<Json, String> {
+    let mut result = match constants {
+        Json::Object(map) => map.clone(),
+        _ => return Err("constants must be a JSON object".to_string()),
+    };
+
+    for override_set in overrides {
+        let override_map = match override_set {
+            Json::Object(map) => map,
+            _ => return Err("overrides must be a JSON object".to_string()),
+        };
+
+        for (key, value) in override_map {
+            let converted = match (result.get(key), value) {
+                (None, _) => return Err(format!("unknown constant {}", key)),
+                (Some(Json::Int(_)), Json::Int(x)) => Json::Int(*x),
+                (Some(Json::Float(_)), Json::Int(x)) => Json::Float(*x as f64),
+                (Some(Json::Float(_)), Json::Float(x)) => Json::Float(*x),
+                (Some(Json::Bool(_)), Json::Bool(x)) => Json::Bool(*x),
+                (Some(Json::Str(_)), Json::Str(x)) => Json::Str(x.clone()),
+                (Some(current), _) => return Err(format!("{} is {} and can't be set to {}", key, current, value)),
+            };
+            result.insert(key.clone(), converted);
+        }
+    }
+
+    Ok(Json::Object(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::constants::Constants;
+    use hlt::json::Json;
+    use hlt::rule_presets;
+
+    // The official 32x32 constants with the preset applied, after checking that the
+    // preset's keys are the only ones that changed.
+    fn applied(name: &str) -> Constants {
+        let official = Constants::official(32).to_json();
+        let overrides = rule_presets::preset(name).unwrap();
+        let result = rule_presets::apply(&official, std::slice::from_ref(&overrides)).unwrap();
+
+        let before = official.as_object().unwrap();
+        let after = result.as_object().unwrap();
+        let changed: Vec<&String> = after.keys().filter(|key| before.get(*key) != after.get(*key)).collect();
+        let expected: Vec<&String> = overrides.as_object().unwrap().keys().collect();
+        assert_eq!(changed, expected, "{}", name);
+        assert_eq!(before.len(), after.len());
+
+        Constants::from_json(&result).unwrap()
+    }
+
+    #[test]
+    fn no_inspiration() {
+        assert!(!applied("no-inspiration").inspiration_enabled);
+    }
+
+    #[test]
+    fn rich_mining() {
+        let constants = applied("rich-mining");
+        assert_eq!((constants.extract_ratio, constants.inspired_extract_ratio), (2, 2));
+    }
+
+    #[test]
+    fn slow_mining() {
+        let constants = applied("slow-mining");
+        assert_eq!((constants.extract_ratio, constants.inspired_extract_ratio), (8, 8));
+    }
+
+    #[test]
+    fn cheap_moves() {
+        let constants = applied("cheap-moves");
+        assert_eq!((constants.move_cost_ratio, constants.inspired_move_cost_ratio), (20, 20));
+    }
+
+    #[test]
+    fn expensive_moves() {
+        let constants = applied("expensive-moves");
+        assert_eq!((constants.move_cost_ratio, constants.inspired_move_cost_ratio), (5, 5));
+    }
+
+    #[test]
+    fn cheap_ships() {
+        assert_eq!(applied("cheap-ships").ship_cost, 500);
+    }
+
+    #[test]
+    fn cheap_dropoffs() {
+        assert_eq!(applied("cheap-dropoffs").dropoff_cost, 2000);
+    }
+
+    #[test]
+    fn short_game() {
+        assert_eq!(applied("short-game").max_turns, 200);
+    }
+
+    #[test]
+    fn capture() {
+        assert!(applied("capture").capture_enabled);
+    }
+
+    #[test]
+    fn every_preset_is_tested() {
+        let names: Vec<&str> = rule_presets::PRESETS.iter().map(|(name, _)| *name).collect();
+        assert_eq!(
+            names,
+            vec![
+                "no-inspiration",
+                "rich-mining",
+                "slow-mining",
+                "cheap-moves",
+                "expensive-moves",
+                "cheap-ships",
+                "cheap-dropoffs",
+                "short-game",
+                "capture"
+            ]
+        );
+        assert!(rule_presets::preset("no-such-preset").is_none());
+    }
+
+    #[test]
+    fn later_overrides_win_and_types_are_kept() {
+        let official = Constants::official(32).to_json();
+        let overrides = [rule_presets::preset("short-game").unwrap(), rule_presets::parse_assignment("MAX_TURNS=150").unwrap()];
+        let result = rule_presets::apply(&official, &overrides).unwrap();
+        assert_eq!(result.get("MAX_TURNS"), Some(&Json::Int(150)));
+
+        let bonus = rule_presets::parse_assignment("INSPIRED_BONUS_MULTIPLIER=3").unwrap();
+        let result = rule_presets::apply(&official, &[bonus]).unwrap();
+        assert_eq!(result.get("INSPIRED_BONUS_MULTIPLIER"), Some(&Json::Float(3.0)));
+
+        assert!(rule_presets::apply(&official, &[rule_presets::parse_assignment("MAX_TURNS=1.5").unwrap()]).is_err());
+        assert!(rule_presets::apply(&official, &[rule_presets::parse_assignment("NO_SUCH_KEY=1").unwrap()]).is_err());
+    }
+}
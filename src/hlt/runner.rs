@@ -0,0 +1,371 @@
+use hlt::command::Command;
+use hlt::constants::Constants;
+use hlt::engine::Engine;
+use hlt::json::Json;
+use hlt::map_generator::MapGenerator;
+use hlt::protocol;
+use hlt::rule_presets;
+use hlt::replay_writer::ReplayWriter;
+use hlt::snapshot::Snapshot;
+use hlt::PlayerId;
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Child;
+use std::process::ChildStdin;
+use std::process::Command as Process;
+use std::process::Stdio;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+pub const INITIAL_HALITE: usize = 5000;
+pub const MAP_SIZES: [usize; 5] = [32, 40, 48, 56, 64];
+
+pub struct MatchConfig {
+    pub bot_commands: Vec<String>,
+    pub width: usize,
+    pub height: usize,
+    pub seed: u64,
+    pub init_timeout: Option<Duration>,
+    pub turn_timeout: Option<Duration>,
+    pub output_dir: PathBuf,
+    pub write_replay: bool,
+    pub constant_overrides: Vec<Json>,
+    // Start from this saved state instead of a generated map; width and height are then
+    // taken from the snapshot.
+    pub snapshot: Option<String>,
+    // Save the state at the start of this turn as snapshot-<turn>.json in the output
+    // directory.
+    pub save_snapshot_at: Option<usize>,
+}
+
+pub struct PlayerResult {
+    pub name: String,
+    pub rank: usize,
+    pub halite: usize,
+    pub ships: usize,
+    pub dropoffs: usize,
+    pub errors: usize,
+    pub terminated: Option<(usize, String)>,
+}
+
+pub struct MatchResult {
+    pub seed: u64,
+    pub width: usize,
+    pub height: usize,
+    pub turns: usize,
+    pub players: Vec<PlayerResult>,
+    pub replay: Option<PathBuf>,
+}
+
+impl MatchResult {
+    pub fn to_json(&self) -> Json {
+        let mut stats = Json::object();
+        for (i, player) in self.players.iter().enumerate() {
+            let terminated = match &player.terminated {
+                Some((turn, reason)) => Json::object()
+                    .with("reason", Json::Str(reason.clone()))
+                    .with("turn", Json::Int(*turn as i64)),
+                None => Json::Null,
+            };
+            let entry = Json::object()
+                .with("dropoffs", Json::Int(player.dropoffs as i64))
+                .with("errors", Json::Int(player.errors as i64))
+                .with("name", Json::Str(player.name.clone()))
+                .with("rank", Json::Int(player.rank as i64))
+                .with("score", Json::Int(player.halite as i64))
+                .with("ships", Json::Int(player.ships as i64))
+                .with("terminated", terminated);
+            stats = stats.with(&i.to_string(), entry);
+        }
+
+        let replay = match &self.replay {
+            Some(path) => Json::Str(path.display().to_string()),
+            None => Json::Null,
+        };
+
+        Json::object()
+            .with("map_height", Json::Int(self.height as i64))
+            .with("map_seed", Json::Int(self.seed as i64))
+            .with("map_width", Json::Int(self.width as i64))
+            .with("replay", replay)
+            .with("stats", stats)
+            .with("turns", Json::Int(self.turns as i64))
+    }
+}
+
+// A bot executable driven over stdin/stdout. Lines from its stdout are read on a
+// separate thread so the runner can wait for them with a timeout.
+struct BotProcess {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    lines: Receiver<String>,
+}
+
+impl BotProcess {
+    fn spawn(command: &str, working_dir: &Path, stderr_path: &Path) -> Result<BotProcess, String> {
+        let stderr = File::create(stderr_path).map_err(|e| format!("can't create {}: {}", stderr_path.display(), e))?;
+        let mut child = Process::new("sh")
+            .arg("-c")
+            .arg(command)
+            .current_dir(working_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::from(stderr))
+            .spawn()
+            .map_err(|e| format!("can't start '{}': {}", command, e))?;
+
+        let stdout = child.stdout.take().unwrap();
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => {
+                        if sender.send(line).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }
+        });
+
+        let stdin = child.stdin.take();
+        Ok(BotProcess { child, stdin, lines })
+    }
+
+    fn send(&mut self, message: &str) -> Result<(), String> {
+        match self.stdin.as_mut() {
+            Some(stdin) => stdin
+                .write_all(message.as_bytes())
+                .and_then(|_| stdin.flush())
+                .map_err(|_| "closed its input".to_string()),
+            None => Err("closed its input".to_string()),
+        }
+    }
+
+    fn receive(&self, deadline: Option<Instant>) -> Result<String, String> {
+        let line = match deadline {
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                self.lines.recv_timeout(remaining).map_err(|e| match e {
+                    RecvTimeoutError::Timeout => "timed out".to_string(),
+                    RecvTimeoutError::Disconnected => "exited".to_string(),
+                })
+            }
+            None => self.lines.recv().map_err(|_| "exited".to_string()),
+        };
+        line.map(|x| x.trim_end_matches('\r').to_string())
+    }
+
+    // Throws away lines the bot printed after its last answer, so that they aren't read
+    // as its commands for the next frame. Returns how many there were.
+    fn discard_pending(&self) -> usize {
+        self.lines.try_iter().count()
+    }
+
+    fn kill(&mut self) {
+        self.stdin = None;
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// Relative executable paths in bot commands are resolved against the directory the
+// runner was started from, because bots run inside the match output directory so that
+// their bot-N.log files end up next to the replay.
+fn resolve_command(command: &str, base: &Path) -> String {
+    let mut resolved: Vec<String> = Vec::new();
+    let mut found_program = false;
+    for token in command.split_whitespace() {
+        if !found_program && !token.contains('=') {
+            found_program = true;
+            if token.contains('/') && !token.starts_with('/') {
+                resolved.push(base.join(token).display().to_string());
+                continue;
+            }
+        }
+        resolved.push(token.to_string());
+    }
+    resolved.join(" ")
+}
+
+pub fn run_match(config: &MatchConfig) -> Result<MatchResult, String> {
+    let num_players = config.bot_commands.len();
+    if num_players != 2 && num_players != 4 {
+        return Err(format!("games need 2 or 4 bots, got {}", num_players));
+    }
+
+    fs::create_dir_all(&config.output_dir)
+        .map_err(|e| format!("can't create {}: {}", config.output_dir.display(), e))?;
+    let base = env::current_dir().map_err(|e| format!("can't read working directory: {}", e))?;
+
+    let (mut game, mut engine) = match &config.snapshot {
+        Some(path) => {
+            let mut snapshot = Snapshot::load(path)?;
+            if snapshot.game.players.len() != num_players {
+                let players = snapshot.game.players.len();
+                return Err(format!("{} has {} players but {} bots were given", path, players, num_players));
+            }
+            let constants = rule_presets::apply(&snapshot.game.constants.to_json(), &config.constant_overrides)?;
+            snapshot.game.constants = Constants::from_json(&constants)?;
+            let engine = Engine::resume(snapshot.next_ship_id, snapshot.next_dropoff_id);
+            (snapshot.game, engine)
+        }
+        None => {
+            let official = Constants::official(config.width.max(config.height)).to_json();
+            let constants = Constants::from_json(&rule_presets::apply(&official, &config.constant_overrides)?)?;
+            let game = MapGenerator::new(config.seed).generate_game(
+                config.width,
+                config.height,
+                num_players,
+                constants,
+                INITIAL_HALITE,
+            )?;
+            let engine = Engine::new(&game);
+            (game, engine)
+        }
+    };
+
+    let mut bots: Vec<BotProcess> = Vec::new();
+    for (i, command) in config.bot_commands.iter().enumerate() {
+        let stderr_path = config.output_dir.join(format!("bot-{}.stderr", i));
+        bots.push(BotProcess::spawn(&resolve_command(command, &base), &config.output_dir, &stderr_path)?);
+    }
+
+    let mut terminated: Vec<Option<(usize, String)>> = vec![None; num_players];
+    let mut errors: Vec<usize> = vec![0; num_players];
+    let mut names: Vec<String> = config.bot_commands.clone();
+
+    let init_deadline = config.init_timeout.map(|x| Instant::now() + x);
+    for (i, bot) in bots.iter_mut().enumerate() {
+        if let Err(reason) = bot.send(&protocol::init_message(&game, PlayerId(i))) {
+            terminated[i] = Some((0, reason));
+        }
+    }
+    for (i, bot) in bots.iter_mut().enumerate() {
+        if terminated[i].is_some() {
+            continue;
+        }
+        match bot.receive(init_deadline) {
+            Ok(name) => names[i] = name,
+            Err(reason) => terminated[i] = Some((0, reason)),
+        }
+    }
+    for (i, bot) in bots.iter_mut().enumerate() {
+        if terminated[i].is_some() {
+            bot.kill();
+        }
+    }
+
+    let mut writer = ReplayWriter::new(&game, &names, config.seed);
+    let mut previous_halite = protocol::halite_grid(&game.map);
+
+    // Turn numbers start at 1 on the server.
+    game.turn_number = game.turn_number.max(1);
+    while !Engine::is_over(&game) {
+        if config.save_snapshot_at == Some(game.turn_number) {
+            let (next_ship_id, next_dropoff_id) = engine.next_ids();
+            let snapshot = Snapshot { game: game.view_for(PlayerId(0)), next_ship_id, next_dropoff_id };
+            snapshot.save(&config.output_dir.join(format!("snapshot-{}.json", game.turn_number)).display().to_string())?;
+        }
+
+        let frame = protocol::frame_message(&game, &previous_halite);
+        previous_halite = protocol::halite_grid(&game.map);
+
+        let sent_at = Instant::now();
+        for (i, bot) in bots.iter_mut().enumerate() {
+            if terminated[i].is_none() {
+                errors[i] += bot.discard_pending();
+                if let Err(reason) = bot.send(&frame) {
+                    terminated[i] = Some((game.turn_number, reason));
+                }
+            }
+        }
+
+        let deadline = config.turn_timeout.map(|x| sent_at + x);
+        let mut commands: Vec<Vec<Command>> = vec![Vec::new(); num_players];
+        for (i, bot) in bots.iter_mut().enumerate() {
+            if terminated[i].is_some() {
+                continue;
+            }
+            match bot.receive(deadline) {
+                Ok(line) => {
+                    let (parsed, parse_errors) = protocol::parse_command_line(&line);
+                    commands[i] = parsed;
+                    errors[i] += parse_errors.len();
+                }
+                Err(reason) => {
+                    terminated[i] = Some((game.turn_number, reason));
+                    bot.kill();
+                }
+            }
+        }
+
+        for (i, reason) in terminated.iter().enumerate() {
+            if reason.is_some() {
+                Engine::remove_ships(&mut game, PlayerId(i));
+            }
+        }
+
+        writer.record_turn(&game, &commands);
+        let result = engine.resolve_turn(&mut game, &commands);
+        for (player_id, _) in result.errors {
+            errors[player_id.0] += 1;
+        }
+    }
+    writer.finish(&game);
+
+    for bot in &mut bots {
+        bot.kill();
+    }
+
+    let replay = if config.write_replay {
+        let path = config.output_dir.join(format!("replay-{}.json", config.seed));
+        let mut file = File::create(&path).map_err(|e| format!("can't create {}: {}", path.display(), e))?;
+        writer.write(&mut file).map_err(|e| format!("can't write {}: {}", path.display(), e))?;
+        Some(path)
+    } else {
+        None
+    };
+
+    let mut ranking: Vec<usize> = (0..num_players).collect();
+    ranking.sort_by(|a, b| game.players[*b].halite.cmp(&game.players[*a].halite).then(a.cmp(b)));
+
+    let players = (0..num_players)
+        .map(|i| PlayerResult {
+            name: names[i].clone(),
+            rank: ranking.iter().position(|x| *x == i).unwrap() + 1,
+            halite: game.players[i].halite,
+            ships: game.players[i].ship_ids.len(),
+            dropoffs: game.players[i].dropoff_ids.len(),
+            errors: errors[i],
+            terminated: terminated[i].clone(),
+        })
+        .collect();
+
+    let result = MatchResult {
+        seed: config.seed,
+        width: game.map.width,
+        height: game.map.height,
+        turns: game.turn_number - 1,
+        players,
+        replay,
+    };
+
+    let summary_path = config.output_dir.join("results.json");
+    let text = result.to_json().to_text().map_err(|e| format!("can't write {}: {}", summary_path.display(), e))?;
+    fs::write(&summary_path, format!("{}\n", text))
+        .map_err(|e| format!("can't write {}: {}", summary_path.display(), e))?;
+
+    Ok(result)
+}
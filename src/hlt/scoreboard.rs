@@ -0,0 +1,282 @@
+use hlt::game::Game;
+use hlt::PlayerId;
+
+// One player's economy over one turn. Everything is worked out from the banks, ships and
+// dropoffs of two frames: whatever the bank gained beyond what was spent on ships and
+// dropoffs counts as deposited. That includes cargo lost in collisions on our structures.
+#[derive(Copy, Clone, Default)]
+pub struct LedgerEntry {
+    pub turn: usize,
+    pub bank: usize,
+    pub deposited: usize,
+    pub spent_on_ships: usize,
+    // What the bank paid: the cost less the cargo and cell halite of the ships that
+    // became dropoffs, which can cover all of it.
+    pub spent_on_dropoffs: usize,
+    pub ships_alive: usize,
+    // Includes ships that were destroyed on the turn they were built, which only show up
+    // as a dip in the bank. One whose cost was made up by deposits that turn is missed.
+    pub ships_built: usize,
+    // Taken from other players, and lost to them or to collisions.
+    pub ships_captured: usize,
+    pub ships_lost: usize,
+    pub dropoffs: usize,
+}
+
+pub struct PlayerLedger {
+    pub player_id: PlayerId,
+    pub entries: Vec<LedgerEntry>,
+}
+
+impl PlayerLedger {
+    pub fn latest(&self) -> LedgerEntry {
+        self.entries.last().cloned().unwrap_or_default()
+    }
+
+    pub fn total_deposited(&self) -> usize {
+        self.entries.iter().map(|entry| entry.deposited).sum()
+    }
+
+    pub fn total_spent_on_ships(&self) -> usize {
+        self.entries.iter().map(|entry| entry.spent_on_ships).sum()
+    }
+
+    pub fn total_spent_on_dropoffs(&self) -> usize {
+        self.entries.iter().map(|entry| entry.spent_on_dropoffs).sum()
+    }
+
+    pub fn total_ships_built(&self) -> usize {
+        self.entries.iter().map(|entry| entry.ships_built).sum()
+    }
+
+    pub fn total_ships_captured(&self) -> usize {
+        self.entries.iter().map(|entry| entry.ships_captured).sum()
+    }
+
+    pub fn total_ships_lost(&self) -> usize {
+        self.entries.iter().map(|entry| entry.ships_lost).sum()
+    }
+
+    // Halite deposited per turn, averaged over the last `turns` turns.
+    pub fn income_rate(&self, turns: usize) -> f64 {
+        let recent: Vec<&LedgerEntry> = self.entries.iter().rev().take(turns).collect();
+        if recent.is_empty() {
+            return 0.0;
+        }
+        recent.iter().map(|entry| entry.deposited).sum::<usize>() as f64 / recent.len() as f64
+    }
+}
+
+pub struct Scoreboard {
+    pub players: Vec<PlayerLedger>,
+}
+
+impl Scoreboard {
+    pub fn new(game: &Game) -> Scoreboard {
+        let players = game
+            .players
+            .iter()
+            .map(|player| PlayerLedger { player_id: player.id, entries: Vec::new() })
+            .collect();
+        Scoreboard { players }
+    }
+
+    // Adds an entry for every player for the turn of `next`. Without a previous frame
+    // only the counts are filled in.
+    pub fn update(&mut self, previous: Option<&Game>, next: &Game) {
+        for (ledger, player) in self.players.iter_mut().zip(&next.players) {
+            let mut entry = LedgerEntry {
+                turn: next.turn_number,
+                bank: player.halite,
+                ships_alive: player.ship_ids.len(),
+                dropoffs: player.dropoff_ids.len(),
+                ..LedgerEntry::default()
+            };
+
+            if let Some(previous) = previous {
+                let before = &previous.players[player.id.0];
+                // Ships new to the game were built, ships another player had were captured.
+                let mut built = player.ship_ids.iter().filter(|id| !previous.ships.contains_key(id)).count();
+                let captured = player
+                    .ship_ids
+                    .iter()
+                    .filter(|id| previous.ships.get(id).is_some_and(|ship| ship.owner != player.id))
+                    .count();
+
+                let new_dropoffs: Vec<_> = player
+                    .dropoff_ids
+                    .iter()
+                    .filter(|id| !previous.dropoffs.contains_key(id))
+                    .filter_map(|id| next.dropoffs.get(id))
+                    .collect();
+                let constructed = new_dropoffs.len();
+                let converted: usize = new_dropoffs
+                    .iter()
+                    .map(|dropoff| {
+                        let cargo = previous
+                            .ships
+                            .values()
+                            .find(|ship| ship.owner == player.id && ship.position == dropoff.position)
+                            .map_or(0, |ship| ship.halite);
+                        cargo + previous.map.at_position(&dropoff.position).halite
+                    })
+                    .sum();
+
+                let ship_cost = next.constants.ship_cost as isize;
+                let dropoff_cost = (constructed * next.constants.dropoff_cost) as isize - converted as isize;
+                let mut deposited =
+                    player.halite as isize - before.halite as isize + built as isize * ship_cost + dropoff_cost;
+                while deposited < 0 && ship_cost > 0 {
+                    built += 1;
+                    deposited += ship_cost;
+                }
+
+                entry.deposited = deposited.max(0) as usize;
+                entry.spent_on_ships = built * next.constants.ship_cost;
+                entry.spent_on_dropoffs = dropoff_cost.max(0) as usize;
+                entry.ships_built = built;
+                entry.ships_captured = captured;
+                entry.ships_lost =
+                    (before.ship_ids.len() + built + captured).saturating_sub(entry.ships_alive + constructed);
+            }
+            ledger.entries.push(entry);
+        }
+    }
+
+    pub fn player(&self, player_id: PlayerId) -> &PlayerLedger {
+        &self.players[player_id.0]
+    }
+
+    pub fn ships_alive(&self, player_id: PlayerId) -> usize {
+        self.player(player_id).latest().ships_alive
+    }
+
+    pub fn enemy_ships_alive(&self, player_id: PlayerId) -> usize {
+        self.players
+            .iter()
+            .filter(|ledger| ledger.player_id != player_id)
+            .map(|ledger| ledger.latest().ships_alive)
+            .sum()
+    }
+
+    // One line per player, for the end of the game.
+    pub fn summary(&self) -> Vec<String> {
+        self.players
+            .iter()
+            .map(|ledger| {
+                let latest = ledger.latest();
+                format!(
+                    "player {}: bank {}, deposited {}, ships {} built {} captured {} lost {} ({} spent), dropoffs {} ({} spent)",
+                    ledger.player_id.0,
+                    latest.bank,
+                    ledger.total_deposited(),
+                    latest.ships_alive,
+                    ledger.total_ships_built(),
+                    ledger.total_ships_captured(),
+                    ledger.total_ships_lost(),
+                    ledger.total_spent_on_ships(),
+                    latest.dropoffs,
+                    ledger.total_spent_on_dropoffs()
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::command::Command;
+    use hlt::direction::Direction;
+    use hlt::game::Game;
+    use hlt::scoreboard::LedgerEntry;
+    use hlt::scoreboard::Scoreboard;
+    use hlt::test_game::*;
+    use hlt::PlayerId;
+    use hlt::ShipId;
+
+    const YARDS: [(i32, i32); 2] = [(2, 2), (13, 13)];
+
+    // The entries of both players for one engine turn.
+    fn ledger(game: &mut Game, commands: &[Vec<Command>]) -> (LedgerEntry, LedgerEntry) {
+        let mut scoreboard = Scoreboard::new(game);
+        scoreboard.update(None, game);
+        let (previous, _) = step(game, commands);
+        scoreboard.update(Some(&previous), game);
+        (scoreboard.player(PlayerId(0)).latest(), scoreboard.player(PlayerId(1)).latest())
+    }
+
+    #[test]
+    fn deposits_and_spawns() {
+        let mut game = game(16, &YARDS);
+        put_halite(&mut game, 6, 6, 0);
+        add_dropoff(&mut game, 0, 0, 6, 6);
+        add_ship(&mut game, 0, 0, 6, 5, 300);
+
+        let (entry, _) = ledger(&mut game, &[vec![Command::Move(ShipId(0), Direction::South), Command::Spawn], vec![]]);
+        assert_eq!(entry.bank, 5000 - 1000 + 290);
+        assert_eq!(entry.deposited, 290);
+        assert_eq!(entry.spent_on_ships, 1000);
+        assert_eq!(entry.ships_built, 1);
+        assert_eq!(entry.ships_alive, 2);
+        assert_eq!(entry.ships_lost, 0);
+    }
+
+    #[test]
+    fn a_dropoff_costs_what_the_cargo_and_cell_do_not_cover() {
+        let mut game = game(16, &YARDS);
+        put_halite(&mut game, 5, 5, 200);
+        add_ship(&mut game, 0, 0, 5, 5, 300);
+        put_halite(&mut game, 9, 9, 3500);
+        add_ship(&mut game, 1, 1, 9, 9, 1000);
+
+        let commands = vec![vec![Command::Construct(ShipId(0))], vec![Command::Construct(ShipId(1))]];
+        let (mine, theirs) = ledger(&mut game, &commands);
+
+        assert_eq!(mine.bank, 5000 - 3500);
+        assert_eq!(mine.spent_on_dropoffs, 3500);
+        assert_eq!(mine.deposited, 0);
+        assert_eq!(mine.dropoffs, 1);
+        assert_eq!(mine.ships_lost, 0);
+
+        // 4500 brought for a 4000 dropoff: the bank gains 500, none of it deposited.
+        assert_eq!(theirs.bank, 5500);
+        assert_eq!(theirs.spent_on_dropoffs, 0);
+        assert_eq!(theirs.deposited, 0);
+        assert_eq!(theirs.ships_built, 0);
+    }
+
+    #[test]
+    fn captured_ships_are_neither_built_nor_paid_for() {
+        let mut game = game(16, &YARDS);
+        game.constants.capture_enabled = true;
+        add_ship(&mut game, 0, 0, 5, 5, 0);
+        add_ship(&mut game, 1, 1, 5, 6, 0);
+        add_ship(&mut game, 1, 2, 6, 5, 0);
+        add_ship(&mut game, 1, 3, 4, 5, 0);
+        add_ship(&mut game, 1, 4, 5, 4, 0);
+
+        let (victim, capturer) = ledger(&mut game, &[vec![], vec![]]);
+        assert_eq!(victim.ships_alive, 0);
+        assert_eq!(victim.ships_lost, 1);
+        assert_eq!(capturer.ships_alive, 5);
+        assert_eq!(capturer.ships_captured, 1);
+        assert_eq!(capturer.ships_built, 0);
+        assert_eq!(capturer.spent_on_ships, 0);
+        assert_eq!(capturer.deposited, 0);
+        assert_eq!(capturer.ships_lost, 0);
+    }
+
+    #[test]
+    fn a_ship_destroyed_as_it_is_built_shows_in_the_bank() {
+        let mut game = game(16, &YARDS);
+        add_ship(&mut game, 0, 0, 2, 2, 50);
+
+        let (entry, _) = ledger(&mut game, &[vec![Command::Spawn], vec![]]);
+        assert_eq!(entry.bank, 4050);
+        assert_eq!(entry.ships_built, 1);
+        assert_eq!(entry.spent_on_ships, 1000);
+        // The wreck's cargo lands in our bank.
+        assert_eq!(entry.deposited, 50);
+        assert_eq!(entry.ships_lost, 2);
+    }
+}
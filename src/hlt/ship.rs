@@ -4,8 +4,10 @@ use hlt::entity::Entity;
 use hlt::input::Input;
 use hlt::PlayerId;
 use hlt::position::Position;
+use hlt::protocol_error::ProtocolError;
 use hlt::ShipId;
 
+#[derive(Clone)]
 pub struct Ship {
     pub owner: PlayerId,
     pub id: ShipId,
@@ -15,6 +17,10 @@ pub struct Ship {
 }
 
 impl Ship {
+    pub fn new(owner: PlayerId, id: ShipId, position: Position, halite: usize, max_halite: usize) -> Ship {
+        Ship { owner, id, position, halite, max_halite }
+    }
+
     pub fn is_full(&self) -> bool {
         self.halite >= self.max_halite
     }
@@ -31,14 +37,14 @@ impl Ship {
         Command::move_ship(self.id, Direction::Still)
     }
 
-    pub fn generate(input: &mut Input, player_id: PlayerId, max_halite: usize) -> Ship {
-        input.read_and_parse_line();
-        let id = ShipId(input.next_usize());
-        let x = input.next_i32();
-        let y = input.next_i32();
-        let halite = input.next_usize();
+    pub fn generate(input: &mut Input, player_id: PlayerId, max_halite: usize) -> Result<Ship, ProtocolError> {
+        input.read_and_parse_line()?;
+        let id = ShipId(input.next_usize()?);
+        let x = input.next_i32()?;
+        let y = input.next_i32()?;
+        let halite = input.next_usize()?;
 
-        Ship { owner: player_id, id, position: Position { x, y }, halite, max_halite }
+        Ok(Ship { owner: player_id, id, position: Position { x, y }, halite, max_halite })
     }
 }
 
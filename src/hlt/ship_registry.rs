@@ -0,0 +1,255 @@
+use hlt::game::Game;
+use hlt::game_event::GameEvent;
+use hlt::position::Position;
+use hlt::PlayerId;
+use hlt::ShipId;
+use std::collections::HashMap;
+
+// Turns here are the turn a change happened on: a ship gone from the frame of turn 8
+// was destroyed on turn 7.
+#[derive(Copy, Clone, PartialEq)]
+pub enum ShipStatus {
+    // First seen in the latest frame given to update. The next update makes it Alive,
+    // Destroyed or Converted, so a ship is Spawned for exactly one frame.
+    Spawned,
+    Alive,
+    Destroyed { turn: usize, position: Position },
+    // Turned into a dropoff.
+    Converted { turn: usize, position: Position },
+}
+
+pub struct ShipHistory {
+    pub id: ShipId,
+    pub owner: PlayerId,
+    pub first_seen_turn: usize,
+    pub status: ShipStatus,
+    // Owners the ship had before it was captured, with the turn it changed hands.
+    pub previous_owners: Vec<(usize, PlayerId)>,
+    // One entry per frame the ship was in, starting with `first_seen_turn`.
+    pub positions: Vec<Position>,
+    pub cargo: Vec<usize>,
+}
+
+impl ShipHistory {
+    pub fn is_alive(&self) -> bool {
+        match self.status {
+            ShipStatus::Spawned | ShipStatus::Alive => true,
+            ShipStatus::Destroyed { .. } | ShipStatus::Converted { .. } => false,
+        }
+    }
+
+    // Frames the ship has been in so far.
+    pub fn age(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn last_position(&self) -> Position {
+        *self.positions.last().unwrap()
+    }
+
+    // Where the ship was the frame before its latest one.
+    pub fn previous_position(&self) -> Option<Position> {
+        self.positions.iter().rev().nth(1).cloned()
+    }
+}
+
+// Every ship of every player seen since the bot started, kept after they are gone.
+#[derive(Default)]
+pub struct ShipRegistry {
+    pub ships: HashMap<ShipId, ShipHistory>,
+}
+
+impl ShipRegistry {
+    pub fn new() -> ShipRegistry {
+        ShipRegistry::default()
+    }
+
+    // Records a frame. `events` are the ones derived for it, used to place collisions.
+    // Returns the ships that disappeared or were captured since the last frame, sorted
+    // by id.
+    pub fn update(&mut self, game: &Game, events: &[GameEvent]) -> Vec<ShipId> {
+        let turn = game.turn_number;
+        let mut captured: Vec<ShipId> = Vec::new();
+
+        for ship in game.ships.values() {
+            match self.ships.get_mut(&ship.id) {
+                Some(history) if history.is_alive() => {
+                    if history.owner != ship.owner {
+                        history.previous_owners.push((turn.saturating_sub(1), history.owner));
+                        history.owner = ship.owner;
+                        captured.push(ship.id);
+                    }
+                    history.status = ShipStatus::Alive;
+                    history.positions.push(ship.position);
+                    history.cargo.push(ship.halite);
+                }
+                // An id handed out again, which only happens when a game was resumed from
+                // a replay; start over.
+                _ => {
+                    let history = ShipHistory {
+                        id: ship.id,
+                        owner: ship.owner,
+                        first_seen_turn: turn,
+                        status: ShipStatus::Spawned,
+                        previous_owners: Vec::new(),
+                        positions: vec![ship.position],
+                        cargo: vec![ship.halite],
+                    };
+                    self.ships.insert(ship.id, history);
+                }
+            }
+        }
+
+        let mut gone: Vec<ShipId> = self
+            .ships
+            .values()
+            .filter(|history| history.is_alive() && !game.ships.contains_key(&history.id))
+            .map(|history| history.id)
+            .collect();
+        gone.sort_by_key(|id| id.0);
+
+        for ship_id in &gone {
+            let history = self.ships.get_mut(ship_id).unwrap();
+            let turn = turn.saturating_sub(1);
+            history.status = ShipStatus::Destroyed { turn, position: history.last_position() };
+            for event in events {
+                match event {
+                    GameEvent::DropoffConstructed { ship_id: Some(id), position, .. } if id == ship_id => {
+                        history.status = ShipStatus::Converted { turn, position: *position };
+                    }
+                    GameEvent::ShipsCollided { position, ship_ids, .. } if ship_ids.contains(ship_id) => {
+                        history.status = ShipStatus::Destroyed { turn, position: *position };
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        gone.extend(captured);
+        gone.sort_by_key(|id| id.0);
+        gone
+    }
+
+    pub fn get(&self, ship_id: &ShipId) -> Option<&ShipHistory> {
+        self.ships.get(ship_id)
+    }
+
+    pub fn destroyed(&self) -> Vec<&ShipHistory> {
+        self.ships
+            .values()
+            .filter(|history| matches!(history.status, ShipStatus::Destroyed { .. }))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::command::Command;
+    use hlt::direction::Direction;
+    use hlt::game::Game;
+    use hlt::game_event::GameEvent;
+    use hlt::ship_registry::ShipRegistry;
+    use hlt::ship_registry::ShipStatus;
+    use hlt::test_game::*;
+    use hlt::PlayerId;
+    use hlt::ShipId;
+
+    const YARDS: [(i32, i32); 2] = [(2, 2), (13, 13)];
+
+    // Plays a turn and records the frame it ends on.
+    fn play(registry: &mut ShipRegistry, game: &mut Game, commands: &[Vec<Command>]) -> Vec<ShipId> {
+        let (previous, _) = step(game, commands);
+        registry.update(game, &GameEvent::derive(&previous, game))
+    }
+
+    #[test]
+    fn a_captured_ship_keeps_its_history() {
+        let mut game = game(16, &YARDS);
+        game.constants.capture_enabled = true;
+        add_ship(&mut game, 0, 0, 5, 5, 0);
+        add_ship(&mut game, 1, 1, 5, 6, 0);
+        add_ship(&mut game, 1, 2, 6, 5, 0);
+        add_ship(&mut game, 1, 3, 4, 5, 0);
+        add_ship(&mut game, 1, 4, 5, 4, 0);
+
+        let mut registry = ShipRegistry::new();
+        assert!(registry.update(&game, &[]).is_empty());
+        let changed = play(&mut registry, &mut game, &[vec![], vec![]]);
+        assert!(changed == vec![ShipId(0)]);
+
+        let history = registry.get(&ShipId(0)).unwrap();
+        assert!(history.owner == PlayerId(1));
+        assert!(history.previous_owners == vec![(1, PlayerId(0))]);
+        assert!(history.status == ShipStatus::Alive);
+        assert_eq!(history.age(), 2);
+        // Mined while surrounded, so inspired: 25 and a bonus of 50.
+        assert!(history.cargo == vec![0, 75]);
+    }
+
+    #[test]
+    fn conversion_and_destruction_are_told_apart() {
+        let mut game = game(16, &YARDS);
+        add_ship(&mut game, 0, 0, 8, 8, 300);
+        add_ship(&mut game, 0, 1, 3, 4, 100);
+        add_ship(&mut game, 1, 2, 4, 5, 50);
+
+        let mut registry = ShipRegistry::new();
+        registry.update(&game, &[]);
+        let commands = vec![
+            vec![Command::Construct(ShipId(0)), Command::Move(ShipId(1), Direction::South)],
+            vec![Command::Move(ShipId(2), Direction::West)],
+        ];
+        let changed = play(&mut registry, &mut game, &commands);
+        assert!(changed == vec![ShipId(0), ShipId(1), ShipId(2)]);
+
+        assert!(registry.get(&ShipId(0)).unwrap().status == ShipStatus::Converted { turn: 1, position: pos(8, 8) });
+        assert!(registry.get(&ShipId(1)).unwrap().status == ShipStatus::Destroyed { turn: 1, position: pos(3, 5) });
+        assert!(registry.get(&ShipId(2)).unwrap().status == ShipStatus::Destroyed { turn: 1, position: pos(3, 5) });
+        assert_eq!(registry.destroyed().len(), 2);
+        assert!(!registry.get(&ShipId(0)).unwrap().is_alive());
+    }
+
+    #[test]
+    fn a_ship_is_spawned_for_one_frame() {
+        let mut game = game(16, &YARDS);
+        let mut registry = ShipRegistry::new();
+        registry.update(&game, &[]);
+
+        play(&mut registry, &mut game, &[vec![Command::Spawn], vec![]]);
+        let history = registry.get(&ShipId(0)).unwrap();
+        assert!(history.status == ShipStatus::Spawned);
+        assert_eq!(history.first_seen_turn, 2);
+        assert!(history.is_alive());
+
+        play(&mut registry, &mut game, &[vec![Command::Move(ShipId(0), Direction::East)], vec![]]);
+        let history = registry.get(&ShipId(0)).unwrap();
+        assert!(history.status == ShipStatus::Alive);
+        assert!(history.previous_position() == Some(pos(2, 2)));
+        assert!(history.last_position() == pos(3, 2));
+    }
+
+    #[test]
+    fn a_reused_id_starts_a_new_history() {
+        let mut game = game(16, &YARDS);
+        add_ship(&mut game, 0, 0, 3, 4, 100);
+        add_ship(&mut game, 1, 1, 4, 5, 50);
+        let mut registry = ShipRegistry::new();
+        registry.update(&game, &[]);
+
+        let commands = vec![vec![Command::Move(ShipId(0), Direction::South)], vec![Command::Move(ShipId(1), Direction::West)]];
+        play(&mut registry, &mut game, &commands);
+        assert!(!registry.get(&ShipId(0)).unwrap().is_alive());
+
+        // As in a game resumed from a replay, where the ids start over.
+        add_ship(&mut game, 1, 0, 13, 13, 0);
+        game.turn_number = 5;
+        assert!(registry.update(&game, &[]).is_empty());
+
+        let history = registry.get(&ShipId(0)).unwrap();
+        assert!(history.status == ShipStatus::Spawned);
+        assert!(history.owner == PlayerId(1));
+        assert_eq!(history.first_seen_turn, 5);
+        assert_eq!(history.age(), 1);
+        assert!(history.previous_owners.is_empty());
+    }
+}
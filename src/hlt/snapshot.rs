@@ -0,0 +1,363 @@
+use hlt::constants::Constants;
+use hlt::dropoff::Dropoff;
+use hlt::game::Game;
+use hlt::game_map::GameMap;
+use hlt::json::Json;
+use hlt::map_cell::MapCell;
+use hlt::map_cell::Structure;
+use hlt::player::Player;
+use hlt::position::Position;
+use hlt::replay_reader::Replay;
+use hlt::ship::Ship;
+use hlt::shipyard::Shipyard;
+use hlt::transcript::Transcript;
+use hlt::DropoffId;
+use hlt::PlayerId;
+use hlt::ShipId;
+use std::fs;
+use std::io;
+
+pub const SNAPSHOT_VERSION: i64 = 1;
+
+// Everything needed to carry on a game from the start of `game.turn_number`: the game
+// itself plus the ids the engine hands out next, which can't be told from the game once
+// the newest ships have been destroyed.
+pub struct Snapshot {
+    pub game: Game,
+    pub next_ship_id: usize,
+    pub next_dropoff_id: usize,
+}
+
+impl Snapshot {
+    // Takes the next ids to be one past the highest ones in the game.
+    pub fn of(game: &Game) -> Snapshot {
+        Snapshot {
+            game: game.view_for(PlayerId(0)),
+            next_ship_id: game.ships.keys().map(|id| id.0 + 1).max().unwrap_or(0),
+            next_dropoff_id: game.dropoffs.keys().map(|id| id.0 + 1).max().unwrap_or(0),
+        }
+    }
+
+    // The state at the start of `turn` in a replay. Ships destroyed on the turn they were
+    // built never show up in a replay, so their ids may be handed out again.
+    pub fn from_replay(replay: &Replay, turn: usize) -> Result<Snapshot, String> {
+        let frame = replay
+            .frames
+            .get(turn)
+            .ok_or_else(|| format!("the replay has turns 0 to {}", replay.frames.len().saturating_sub(1)))?;
+        let mut snapshot = Snapshot::of(&frame.game);
+        snapshot.game.turn_number = turn.max(1);
+        for earlier in &replay.frames[..turn] {
+            let ships = earlier.game.ships.keys().map(|id| id.0 + 1);
+            let dropoffs = earlier.game.dropoffs.keys().map(|id| id.0 + 1);
+            snapshot.next_ship_id = ships.fold(snapshot.next_ship_id, usize::max);
+            snapshot.next_dropoff_id = dropoffs.fold(snapshot.next_dropoff_id, usize::max);
+        }
+        Ok(snapshot)
+    }
+
+    // The state at the start of `turn` of a game a bot played, read back from its
+    // transcript. As with replays, ships destroyed on the turn they were built are missed.
+    pub fn from_transcript(transcript: &Transcript, turn: usize) -> Result<Snapshot, String> {
+        let mut game = Game::from_io(transcript.engine_input(), Box::new(io::sink()))?;
+        let (mut next_ship_id, mut next_dropoff_id) = (0, 0);
+        while game.turn_number < turn.max(1) {
+            game.update_frame().map_err(|_| format!("the transcript ends at turn {}", game.turn_number))?;
+            let ids = Snapshot::of(&game);
+            next_ship_id = next_ship_id.max(ids.next_ship_id);
+            next_dropoff_id = next_dropoff_id.max(ids.next_dropoff_id);
+        }
+
+        let mut snapshot = Snapshot::of(&game);
+        snapshot.next_ship_id = next_ship_id;
+        snapshot.next_dropoff_id = next_dropoff_id;
+        Ok(snapshot)
+    }
+
+    pub fn load(path: &str) -> Result<Snapshot, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("can't read snapshot {}: {}", path, e))?;
+        let json = Json::parse(&text).map_err(|e| format!("{}: {}", path, e))?;
+        Snapshot::from_json(&json).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let text = self.to_json().to_text().map_err(|e| format!("can't write {}: {}", path, e))?;
+        fs::write(path, format!("{}\n", text)).map_err(|e| format!("can't write {}: {}", path, e))
+    }
+
+    pub fn to_json(&self) -> Json {
+        let game = &self.game;
+        let halite = game
+            .map
+            .cells
+            .iter()
+            .map(|row| Json::Array(row.iter().map(|cell| Json::Int(cell.halite as i64)).collect()))
+            .collect();
+
+        let players = game
+            .players
+            .iter()
+            .map(|player| {
+                Json::object()
+                    .with("halite", Json::Int(player.halite as i64))
+                    .with("id", Json::Int(player.id.0 as i64))
+                    .with("shipyard", position_json(&player.shipyard.position))
+            })
+            .collect();
+
+        let mut ship_ids: Vec<&ShipId> = game.ships.keys().collect();
+        ship_ids.sort_by_key(|id| id.0);
+        let ships = ship_ids
+            .iter()
+            .map(|id| {
+                let ship = &game.ships[id];
+                position_json(&ship.position)
+                    .with("halite", Json::Int(ship.halite as i64))
+                    .with("id", Json::Int(ship.id.0 as i64))
+                    .with("owner", Json::Int(ship.owner.0 as i64))
+            })
+            .collect();
+
+        let mut dropoff_ids: Vec<&DropoffId> = game.dropoffs.keys().collect();
+        dropoff_ids.sort_by_key(|id| id.0);
+        let dropoffs = dropoff_ids
+            .iter()
+            .map(|id| {
+                let dropoff = &game.dropoffs[id];
+                position_json(&dropoff.position)
+                    .with("id", Json::Int(dropoff.id.0 as i64))
+                    .with("owner", Json::Int(dropoff.owner.0 as i64))
+            })
+            .collect();
+
+        Json::object()
+            .with("constants", game.constants.to_json())
+            .with("dropoffs", Json::Array(dropoffs))
+            .with("halite", Json::Array(halite))
+            .with("height", Json::Int(game.map.height as i64))
+            .with("initial_halite", Json::Int(game.map.initial_halite as i64))
+            .with("next_dropoff_id", Json::Int(self.next_dropoff_id as i64))
+            .with("next_ship_id", Json::Int(self.next_ship_id as i64))
+            .with("players", Json::Array(players))
+            .with("ships", Json::Array(ships))
+            .with("turn", Json::Int(game.turn_number as i64))
+            .with("version", Json::Int(SNAPSHOT_VERSION))
+            .with("width", Json::Int(game.map.width as i64))
+    }
+
+    pub fn from_json(json: &Json) -> Result<Snapshot, String> {
+        let version = field(json, "version")?.as_i64();
+        if version != Some(SNAPSHOT_VERSION) {
+            return Err(format!("expected snapshot version {}", SNAPSHOT_VERSION));
+        }
+
+        let constants = Constants::from_json(field(json, "constants")?)?;
+        let width = usize_field(json, "width")?;
+        let height = usize_field(json, "height")?;
+
+        let rows = array(field(json, "halite")?)?;
+        if rows.len() != height {
+            return Err("halite does not match the height".to_string());
+        }
+        let mut cells: Vec<Vec<MapCell>> = Vec::with_capacity(height);
+        for (y, row) in rows.iter().enumerate() {
+            let row = array(row)?;
+            if row.len() != width {
+                return Err("halite does not match the width".to_string());
+            }
+            let mut cell_row: Vec<MapCell> = Vec::with_capacity(width);
+            for (x, halite) in row.iter().enumerate() {
+                let halite = halite.as_usize().ok_or_else(|| "halite must be non-negative integers".to_string())?;
+                let position = Position { x: x as i32, y: y as i32 };
+                cell_row.push(MapCell { position, halite, structure: Structure::None });
+            }
+            cells.push(cell_row);
+        }
+        let mut map = GameMap::new(width, height, cells);
+        if let Some(initial_halite) = json.get("initial_halite").and_then(Json::as_usize) {
+            map.initial_halite = initial_halite;
+        }
+
+        let mut players: Vec<Player> = Vec::new();
+        for player_json in array(field(json, "players")?)? {
+            let id = PlayerId(usize_field(player_json, "id")?);
+            let position = position(field(player_json, "shipyard")?, width, height)?;
+            players.push(Player {
+                id,
+                shipyard: Shipyard { owner: id, position },
+                halite: usize_field(player_json, "halite")?,
+                ship_ids: Vec::new(),
+                dropoff_ids: Vec::new(),
+            });
+        }
+        if players.iter().enumerate().any(|(i, player)| player.id.0 != i) {
+            return Err("player ids must run from 0 to the number of players - 1".to_string());
+        }
+
+        let mut game = Game::construct(constants, PlayerId(0), players, map);
+        game.turn_number = usize_field(json, "turn")?;
+
+        for dropoff_json in array(field(json, "dropoffs")?)? {
+            let dropoff = Dropoff {
+                owner: owner(dropoff_json, &game)?,
+                id: DropoffId(usize_field(dropoff_json, "id")?),
+                position: position(dropoff_json, width, height)?,
+            };
+            game.players[dropoff.owner.0].dropoff_ids.push(dropoff.id);
+            game.map.at_position_mut(&dropoff.position).structure = Structure::Dropoff(dropoff.id);
+            game.dropoffs.insert(dropoff.id, dropoff);
+        }
+
+        for ship_json in array(field(json, "ships")?)? {
+            let owner = owner(ship_json, &game)?;
+            let id = ShipId(usize_field(ship_json, "id")?);
+            let position = position(ship_json, width, height)?;
+            let halite = usize_field(ship_json, "halite")?;
+            if game.ships.contains_key(&id) {
+                return Err(format!("ship {} is listed twice", id.0));
+            }
+            game.players[owner.0].ship_ids.push(id);
+            game.ships.insert(id, Ship::new(owner, id, position, halite, game.constants.max_halite));
+        }
+        for player in &mut game.players {
+            player.ship_ids.sort_by_key(|id| id.0);
+            player.dropoff_ids.sort_by_key(|id| id.0);
+        }
+
+        let mut snapshot = Snapshot::of(&game);
+        if let Some(next_ship_id) = json.get("next_ship_id").and_then(Json::as_usize) {
+            snapshot.next_ship_id = snapshot.next_ship_id.max(next_ship_id);
+        }
+        if let Some(next_dropoff_id) = json.get("next_dropoff_id").and_then(Json::as_usize) {
+            snapshot.next_dropoff_id = snapshot.next_dropoff_id.max(next_dropoff_id);
+        }
+        Ok(snapshot)
+    }
+}
+
+fn position_json(position: &Position) -> Json {
+    Json::object()
+        .with("x", Json::Int(position.x as i64))
+        .with("y", Json::Int(position.y as i64))
+}
+
+fn field<'a>(json: &'a Json, key: &str) -> Result<&'a Json, String> {
+    json.get(key).ok_or_else(|| format!("snapshot is missing '{}'", key))
+}
+
+fn usize_field(json: &Json, key: &str) -> Result<usize, String> {
+    field(json, key)?.as_usize().ok_or_else(|| format!("'{}' is not a non-negative integer", key))
+}
+
+fn array(json: &Json) -> Result<&Vec<Json>, String> {
+    json.as_array().ok_or_else(|| "expected an array".to_string())
+}
+
+fn position(json: &Json, width: usize, height: usize) -> Result<Position, String> {
+    let x = usize_field(json, "x")?;
+    let y = usize_field(json, "y")?;
+    if x >= width || y >= height {
+        return Err(format!("({}, {}) is off the map", x, y));
+    }
+    Ok(Position { x: x as i32, y: y as i32 })
+}
+
+fn owner(json: &Json, game: &Game) -> Result<PlayerId, String> {
+    let owner = usize_field(json, "owner")?;
+    if owner >= game.players.len() {
+        return Err(format!("there is no player {}", owner));
+    }
+    Ok(PlayerId(owner))
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::arena::Arena;
+    use hlt::baseline::GreedyMiner;
+    use hlt::baseline::Rammer;
+    use hlt::command::Command;
+    use hlt::json::Json;
+    use hlt::replay_reader::Replay;
+    use hlt::snapshot::Snapshot;
+    use hlt::strategy::Strategy;
+    use hlt::PlayerId;
+    use hlt::ShipId;
+
+    const REPLAY: &str = include_str!("../../tests/data/replay-2p-32x32.json");
+
+    // Through text and back, as save and load do it.
+    fn reloaded(snapshot: &Snapshot) -> Snapshot {
+        Snapshot::from_json(&Json::parse(&snapshot.to_json().to_text().unwrap()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let mut arena = Arena::generate(4, 32, 32, 2).unwrap();
+        let mut strategies: Vec<Box<dyn Strategy>> =
+            vec![Box::new(GreedyMiner::new(&arena.view(PlayerId(0)))), Box::new(Rammer::new(&arena.view(PlayerId(1))))];
+        for _ in 0..40 {
+            arena.step(&mut strategies);
+        }
+
+        let mut snapshot = arena.snapshot();
+        assert!(!snapshot.game.ships.is_empty());
+        // Ids the game can't tell: the newest ships and dropoffs are gone.
+        snapshot.next_ship_id += 5;
+        snapshot.next_dropoff_id = 3;
+
+        let copy = reloaded(&snapshot);
+        assert_eq!(copy.to_json(), snapshot.to_json());
+        assert_eq!(copy.next_ship_id, snapshot.next_ship_id);
+        assert_eq!(copy.next_dropoff_id, 3);
+        assert_eq!(copy.game.turn_number, 41);
+        assert_eq!(copy.game.map.initial_halite, arena.game.map.initial_halite);
+        assert_eq!(copy.game.map.current_halite, arena.game.map.current_halite);
+        for player in &arena.game.players {
+            assert!(copy.game.players[player.id.0].ship_ids == player.ship_ids);
+        }
+    }
+
+    #[test]
+    fn a_resumed_game_carries_on_identically() {
+        let mut arena = Arena::generate(6, 32, 32, 2).unwrap();
+        let mut strategies: Vec<Box<dyn Strategy>> =
+            vec![Box::new(Rammer::new(&arena.view(PlayerId(0)))), Box::new(GreedyMiner::new(&arena.view(PlayerId(1))))];
+        for _ in 0..60 {
+            arena.step(&mut strategies);
+        }
+
+        let mut resumed = Arena::from_snapshot(reloaded(&arena.snapshot()));
+        while !arena.is_over() {
+            let commands: Vec<Vec<Command>> = strategies
+                .iter_mut()
+                .enumerate()
+                .map(|(i, strategy)| strategy.play_turn(&arena.view(PlayerId(i))))
+                .collect();
+            let expected = arena.resolve(&commands);
+            let result = resumed.resolve(&commands);
+
+            assert!(result.spawned == expected.spawned);
+            assert_eq!(resumed.snapshot().to_json(), arena.snapshot().to_json(), "turn {}", arena.game.turn_number);
+        }
+        assert!(resumed.is_over());
+    }
+
+    #[test]
+    fn from_replay_keeps_ids_of_ships_already_gone() {
+        let replay = Replay::parse(REPLAY).unwrap();
+        let snapshot = Snapshot::from_replay(&replay, 5).unwrap();
+        assert_eq!(snapshot.game.turn_number, 5);
+        assert!(snapshot.game.ships.is_empty());
+        assert_eq!(snapshot.game.dropoffs.len(), 1);
+        assert_eq!((snapshot.game.players[0].halite, snapshot.game.players[1].halite), (3009, 88));
+        // Ships 0 to 2 were in earlier frames.
+        assert_eq!((snapshot.next_ship_id, snapshot.next_dropoff_id), (3, 1));
+
+        let mut arena = Arena::from_snapshot(reloaded(&snapshot));
+        let result = arena.resolve(&[vec![Command::Spawn], vec![]]);
+        assert!(result.spawned == vec![ShipId(3)]);
+
+        assert_eq!(Snapshot::from_replay(&replay, 0).unwrap().game.turn_number, 1);
+        assert!(Snapshot::from_replay(&replay, replay.frames.len()).is_err());
+    }
+}
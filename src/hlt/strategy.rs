@@ -0,0 +1,23 @@
+use hlt::command::Command;
+use hlt::game::Game;
+use hlt::protocol_error::ProtocolError;
+
+// Anything that can play a seat: gets that player's view of the game each turn and
+// answers with the commands it would send to the server.
+pub trait Strategy {
+    fn name(&self) -> String;
+    fn play_turn(&mut self, game: &Game) -> Vec<Command>;
+}
+
+// Plays a whole game over the game's input and output, for strategies built as their
+// own bot binary. Returns when the input ends or can't be read.
+pub fn run(mut game: Game, strategy: &mut dyn Strategy) -> ProtocolError {
+    game.ready(&strategy.name());
+    loop {
+        if let Err(error) = game.update_frame() {
+            return error;
+        }
+        let commands = strategy.play_turn(&game);
+        game.end_turn(&commands);
+    }
+}
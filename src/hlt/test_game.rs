@@ -0,0 +1,80 @@
+use hlt::constants::Constants;
+use hlt::command::Command;
+use hlt::dropoff::Dropoff;
+use hlt::engine::Engine;
+use hlt::engine::TurnResult;
+use hlt::game::Game;
+use hlt::game_map::GameMap;
+use hlt::map_cell::MapCell;
+use hlt::map_cell::Structure;
+use hlt::player::Player;
+use hlt::position::Position;
+use hlt::ship::Ship;
+use hlt::shipyard::Shipyard;
+use hlt::DropoffId;
+use hlt::PlayerId;
+use hlt::ShipId;
+
+// Small hand-made games for tests: a square map with 100 halite on every cell but the
+// shipyards, the official constants for a 32x32 map, 5000 in every bank and turn 1 to
+// play. Ships and dropoffs are placed by hand.
+
+pub fn pos(x: i32, y: i32) -> Position {
+    Position { x, y }
+}
+
+pub fn game(size: usize, shipyards: &[(i32, i32)]) -> Game {
+    let cells = (0..size as i32)
+        .map(|y| {
+            (0..size as i32)
+                .map(|x| {
+                    let halite = if shipyards.contains(&(x, y)) { 0 } else { 100 };
+                    MapCell { position: pos(x, y), halite, structure: Structure::None }
+                })
+                .collect()
+        })
+        .collect();
+
+    let players = shipyards
+        .iter()
+        .enumerate()
+        .map(|(i, (x, y))| Player {
+            id: PlayerId(i),
+            shipyard: Shipyard { owner: PlayerId(i), position: pos(*x, *y) },
+            halite: 5000,
+            ship_ids: Vec::new(),
+            dropoff_ids: Vec::new(),
+        })
+        .collect();
+
+    let mut game = Game::construct(Constants::official(32), PlayerId(0), players, GameMap::new(size, size, cells));
+    game.turn_number = 1;
+    game
+}
+
+// Sets a cell before the game starts, keeping the map totals as if it had always been so.
+pub fn put_halite(game: &mut Game, x: i32, y: i32, halite: usize) {
+    let mut cells = game.map.cells.clone();
+    cells[y as usize][x as usize].halite = halite;
+    game.map = GameMap::new(game.map.width, game.map.height, cells);
+}
+
+pub fn add_ship(game: &mut Game, owner: usize, id: usize, x: i32, y: i32, halite: usize) {
+    let ship = Ship::new(PlayerId(owner), ShipId(id), pos(x, y), halite, game.constants.max_halite);
+    game.players[owner].ship_ids.push(ShipId(id));
+    game.ships.insert(ShipId(id), ship);
+}
+
+pub fn add_dropoff(game: &mut Game, owner: usize, id: usize, x: i32, y: i32) {
+    let dropoff = Dropoff { owner: PlayerId(owner), id: DropoffId(id), position: pos(x, y) };
+    game.players[owner].dropoff_ids.push(DropoffId(id));
+    game.dropoffs.insert(DropoffId(id), dropoff);
+    game.map.at_position_mut(&pos(x, y)).structure = Structure::Dropoff(DropoffId(id));
+}
+
+// Plays one turn with the engine and returns the frame it started from.
+pub fn step(game: &mut Game, commands: &[Vec<Command>]) -> (Game, TurnResult) {
+    let previous = game.view_for(game.my_id);
+    let result = Engine::new(game).resolve_turn(game, commands);
+    (previous, result)
+}
@@ -0,0 +1,390 @@
+use hlt::json::Json;
+use hlt::rng;
+use hlt::runner;
+use hlt::runner::MatchConfig;
+use hlt::runner::MatchResult;
+use rand::Rng;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+pub const INITIAL_RATING: f64 = 1500.0;
+const K_FACTOR: f64 = 32.0;
+
+#[derive(Clone)]
+pub struct Contestant {
+    pub name: String,
+    pub command: String,
+}
+
+#[derive(Clone)]
+pub struct GameSpec {
+    pub index: usize,
+    pub seed: u64,
+    pub size: usize,
+    pub seats: Vec<usize>,
+}
+
+pub struct Rating {
+    pub rating: f64,
+    pub games: usize,
+    pub wins: usize,
+}
+
+// Ratings per bot name plus one entry per finished game, stored as JSON so that
+// results accumulate over several tournament runs.
+pub struct RatingTable {
+    pub ratings: BTreeMap<String, Rating>,
+    pub history: Vec<Json>,
+}
+
+impl RatingTable {
+    pub fn load(path: &Path) -> Result<RatingTable, String> {
+        if !path.exists() {
+            return Ok(RatingTable { ratings: BTreeMap::new(), history: Vec::new() });
+        }
+
+        let text = fs::read_to_string(path).map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+        let root = Json::parse(&text)?;
+
+        let mut ratings = BTreeMap::new();
+        if let Some(entries) = root.get("ratings").and_then(Json::as_object) {
+            for (name, entry) in entries {
+                let value = entry.get("rating").and_then(Json::as_f64).unwrap_or(INITIAL_RATING);
+                if !value.is_finite() {
+                    return Err(format!("{}: rating of {} is {}", path.display(), name, value));
+                }
+                let rating = Rating {
+                    rating: value,
+                    games: entry.get("games").and_then(Json::as_usize).unwrap_or(0),
+                    wins: entry.get("wins").and_then(Json::as_usize).unwrap_or(0),
+                };
+                ratings.insert(name.clone(), rating);
+            }
+        }
+        let history = root.get("history").and_then(Json::as_array).cloned().unwrap_or_default();
+
+        Ok(RatingTable { ratings, history })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut ratings = Json::object();
+        for (name, rating) in &self.ratings {
+            let entry = Json::object()
+                .with("games", Json::Int(rating.games as i64))
+                .with("rating", Json::Float(rating.rating))
+                .with("wins", Json::Int(rating.wins as i64));
+            ratings = ratings.with(name, entry);
+        }
+        let root = Json::object()
+            .with("history", Json::Array(self.history.clone()))
+            .with("ratings", ratings);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("can't create {}: {}", parent.display(), e))?;
+        }
+        // Written next to the table and renamed so an interrupted run never leaves half a file.
+        let temporary = path.with_extension("tmp");
+        let text = root.to_text().map_err(|e| format!("can't write {}: {}", path.display(), e))?;
+        fs::write(&temporary, format!("{}\n", text)).map_err(|e| format!("can't write {}: {}", temporary.display(), e))?;
+        fs::rename(&temporary, path).map_err(|e| format!("can't write {}: {}", path.display(), e))
+    }
+
+    pub fn rating(&self, name: &str) -> f64 {
+        self.ratings.get(name).map_or(INITIAL_RATING, |x| x.rating)
+    }
+
+    // Multiplayer Elo: every pair of seats in a game counts as one match decided by
+    // rank, with K split over the number of opponents so 4 player games don't count triple.
+    pub fn record(&mut self, names: &[String], result: &MatchResult) {
+        let num_players = names.len();
+        let before: Vec<f64> = names.iter().map(|name| self.rating(name)).collect();
+        let k = K_FACTOR / (num_players - 1) as f64;
+
+        for i in 0..num_players {
+            let mut delta = 0.0;
+            for j in 0..num_players {
+                if i == j {
+                    continue;
+                }
+                let expected = 1.0 / (1.0 + 10f64.powf((before[j] - before[i]) / 400.0));
+                let actual = match result.players[i].rank.cmp(&result.players[j].rank) {
+                    ::std::cmp::Ordering::Less => 1.0,
+                    ::std::cmp::Ordering::Equal => 0.5,
+                    ::std::cmp::Ordering::Greater => 0.0,
+                };
+                delta += k * (actual - expected);
+            }
+
+            let entry = self
+                .ratings
+                .entry(names[i].clone())
+                .or_insert(Rating { rating: INITIAL_RATING, games: 0, wins: 0 });
+            entry.rating += delta;
+            entry.games += 1;
+            if result.players[i].rank == 1 {
+                entry.wins += 1;
+            }
+        }
+
+        let players = names
+            .iter()
+            .zip(&result.players)
+            .map(|(name, player)| {
+                Json::object()
+                    .with("name", Json::Str(name.clone()))
+                    .with("rank", Json::Int(player.rank as i64))
+                    .with("score", Json::Int(player.halite as i64))
+                    .with("terminated", Json::Bool(player.terminated.is_some()))
+            })
+            .collect();
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).map(|x| x.as_secs()).unwrap_or(0);
+        self.history.push(
+            Json::object()
+                .with("height", Json::Int(result.height as i64))
+                .with("players", Json::Array(players))
+                .with("seed", Json::Int(result.seed as i64))
+                .with("time", Json::Int(time as i64))
+                .with("width", Json::Int(result.width as i64)),
+        );
+    }
+
+    pub fn standings(&self) -> Vec<(&String, &Rating)> {
+        let mut standings: Vec<(&String, &Rating)> = self.ratings.iter().collect();
+        standings.sort_by(|a, b| b.1.rating.total_cmp(&a.1.rating).then(a.0.cmp(b.0)));
+        standings
+    }
+}
+
+pub struct TournamentConfig {
+    pub contestants: Vec<Contestant>,
+    pub games: usize,
+    pub seed: u64,
+    pub threads: usize,
+    pub init_timeout: Option<Duration>,
+    pub turn_timeout: Option<Duration>,
+    pub output_dir: PathBuf,
+    pub write_replays: bool,
+    pub ratings_path: PathBuf,
+}
+
+// Picks map size, player count and seats for every game. Four player games are only
+// scheduled when there are at least four contestants, so nobody plays against itself.
+pub fn schedule(num_contestants: usize, games: usize, seed: u64) -> Vec<GameSpec> {
+    let mut rng = rng::seeded(seed);
+    (0..games)
+        .map(|index| {
+            let num_players = if num_contestants >= 4 && rng.gen::<bool>() { 4 } else { 2 };
+            let size = runner::MAP_SIZES[rng.gen_range(0, runner::MAP_SIZES.len())];
+            let mut seats: Vec<usize> = (0..num_contestants).collect();
+            rng.shuffle(&mut seats);
+            seats.truncate(num_players);
+
+            GameSpec { index, seed: seed.wrapping_add(index as u64), size, seats }
+        })
+        .collect()
+}
+
+// Hands back items pushed in any order by their index, each once all earlier ones are in.
+struct InOrder<T> {
+    next: usize,
+    pending: BTreeMap<usize, T>,
+}
+
+impl<T> InOrder<T> {
+    fn new() -> InOrder<T> {
+        InOrder { next: 0, pending: BTreeMap::new() }
+    }
+
+    fn push(&mut self, index: usize, item: T) -> Vec<T> {
+        self.pending.insert(index, item);
+        let mut ready = Vec::new();
+        while let Some(item) = self.pending.remove(&self.next) {
+            ready.push(item);
+            self.next += 1;
+        }
+        ready
+    }
+
+    // Whatever is left after a gap that will never be filled, in index order.
+    fn rest(self) -> Vec<T> {
+        self.pending.into_values().collect()
+    }
+}
+
+// Runs the schedule on `threads` worker threads. `report` is called as games finish,
+// including the ones the runner failed to play. Results are folded into the rating table
+// in game index order, so the ratings don't depend on which game finished first, and the
+// table is saved after each one.
+pub fn run_tournament(
+    config: &TournamentConfig,
+    report: &mut dyn FnMut(&GameSpec, &Result<MatchResult, String>),
+) -> Result<RatingTable, String> {
+    if config.contestants.len() < 2 {
+        return Err("a tournament needs at least 2 bots".to_string());
+    }
+
+    let mut table = RatingTable::load(&config.ratings_path)?;
+    let specs = Arc::new(schedule(config.contestants.len(), config.games, config.seed));
+    let next = Arc::new(AtomicUsize::new(0));
+    let (sender, results) = mpsc::channel();
+
+    let mut workers = Vec::new();
+    for _ in 0..config.threads.max(1) {
+        let specs = specs.clone();
+        let next = next.clone();
+        let sender = sender.clone();
+        let contestants = config.contestants.clone();
+        let init_timeout = config.init_timeout;
+        let turn_timeout = config.turn_timeout;
+        let write_replay = config.write_replays;
+        let output_dir = config.output_dir.clone();
+
+        workers.push(thread::spawn(move || loop {
+            let index = next.fetch_add(1, Ordering::SeqCst);
+            let spec = match specs.get(index) {
+                Some(spec) => spec.clone(),
+                None => break,
+            };
+
+            let game_config = MatchConfig {
+                bot_commands: spec.seats.iter().map(|x| contestants[*x].command.clone()).collect(),
+                width: spec.size,
+                height: spec.size,
+                seed: spec.seed,
+                init_timeout,
+                turn_timeout,
+                output_dir: output_dir.join(format!("{}-{}", spec.index, spec.seed)),
+                write_replay,
+                constant_overrides: Vec::new(),
+                snapshot: None,
+                save_snapshot_at: None,
+            };
+            let result = runner::run_match(&game_config);
+            if sender.send((spec, result)).is_err() {
+                break;
+            }
+        }));
+    }
+    drop(sender);
+
+    let mut in_order = InOrder::new();
+    let record = |table: &mut RatingTable, (spec, result): (GameSpec, Result<MatchResult, String>)| {
+        if let Ok(result) = &result {
+            let names: Vec<String> = spec.seats.iter().map(|x| config.contestants[*x].name.clone()).collect();
+            table.record(&names, result);
+            table.save(&config.ratings_path)?;
+        }
+        Ok::<(), String>(())
+    };
+    for (spec, result) in results {
+        report(&spec, &result);
+        for finished in in_order.push(spec.index, (spec, result)) {
+            record(&mut table, finished)?;
+        }
+    }
+    // Only reached with games missing if a worker thread died.
+    for finished in in_order.rest() {
+        record(&mut table, finished)?;
+    }
+
+    for worker in workers {
+        let _ = worker.join();
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::runner::MatchResult;
+    use hlt::runner::PlayerResult;
+    use hlt::tournament::InOrder;
+    use hlt::tournament::RatingTable;
+    use std::collections::BTreeMap;
+
+    fn table() -> RatingTable {
+        RatingTable { ratings: BTreeMap::new(), history: Vec::new() }
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|x| x.to_string()).collect()
+    }
+
+    fn result(ranks: &[usize]) -> MatchResult {
+        let players = ranks
+            .iter()
+            .map(|rank| PlayerResult {
+                name: String::new(),
+                rank: *rank,
+                halite: 1000 / rank,
+                ships: 0,
+                dropoffs: 0,
+                errors: 0,
+                terminated: None,
+            })
+            .collect();
+        MatchResult { seed: 1, width: 32, height: 32, turns: 400, players, replay: None }
+    }
+
+    fn assert_rating(table: &RatingTable, name: &str, expected: f64) {
+        let rating = table.rating(name);
+        assert!((rating - expected).abs() < 1e-9, "{} has {}, expected {}", name, rating, expected);
+    }
+
+    #[test]
+    fn two_player_elo() {
+        let mut table = table();
+        table.record(&names(&["a", "b"]), &result(&[1, 2]));
+        assert_rating(&table, "a", 1516.0);
+        assert_rating(&table, "b", 1484.0);
+
+        // The underdog wins: b was expected to score 1 / (1 + 10^(32 / 400)).
+        table.record(&names(&["a", "b"]), &result(&[2, 1]));
+        assert_rating(&table, "a", 1498.5304984710244);
+        assert_rating(&table, "b", 1501.4695015289756);
+
+        assert_eq!((table.ratings["a"].games, table.ratings["a"].wins), (2, 1));
+        assert_eq!(table.history.len(), 2);
+    }
+
+    #[test]
+    fn four_player_elo_splits_k_and_shares_ties() {
+        let mut table = table();
+        table.record(&names(&["a", "b", "c", "d"]), &result(&[1, 2, 3, 3]));
+        assert_rating(&table, "a", 1516.0);
+        assert_rating(&table, "b", 1500.0 + 32.0 / 3.0 / 2.0);
+        assert_rating(&table, "c", 1500.0 - 32.0 / 3.0);
+        assert_rating(&table, "d", 1500.0 - 32.0 / 3.0);
+    }
+
+    #[test]
+    fn the_order_games_are_folded_in_matters() {
+        let mut forwards = table();
+        forwards.record(&names(&["a", "b"]), &result(&[1, 2]));
+        forwards.record(&names(&["a", "b"]), &result(&[2, 1]));
+        let mut backwards = table();
+        backwards.record(&names(&["a", "b"]), &result(&[2, 1]));
+        backwards.record(&names(&["a", "b"]), &result(&[1, 2]));
+        assert!((forwards.rating("a") - backwards.rating("a")).abs() > 1.0);
+    }
+
+    #[test]
+    fn results_come_out_in_game_order() {
+        let mut in_order = InOrder::new();
+        assert!(in_order.push(2, "c").is_empty());
+        assert!(in_order.push(1, "b").is_empty());
+        assert_eq!(in_order.push(0, "a"), vec!["a", "b", "c"]);
+        assert_eq!(in_order.push(3, "d"), vec!["d"]);
+        assert!(in_order.push(5, "f").is_empty());
+        assert_eq!(in_order.rest(), vec!["f"]);
+    }
+}
@@ -0,0 +1,261 @@
+use std::cell::RefCell;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::BufRead;
+use std::io::Cursor;
+use std::io::Read;
+use std::io::Write;
+use std::rc::Rc;
+
+// A transcript has one line per protocol line: "< " for what the engine sent us, "> "
+// for what we sent back. The rng seed goes in a "# seed N" line at the top.
+const FROM_ENGINE: &str = "< ";
+const FROM_BOT: &str = "> ";
+const SEED: &str = "# seed ";
+
+struct Recording {
+    file: Option<File>,
+    buffer: Vec<u8>,
+    pending_in: Vec<u8>,
+    pending_out: Vec<u8>,
+}
+
+impl Recording {
+    fn record(&mut self, prefix: &str, bytes: &[u8]) {
+        for &byte in bytes {
+            let pending = if prefix == FROM_ENGINE { &mut self.pending_in } else { &mut self.pending_out };
+            if byte != b'\n' {
+                pending.push(byte);
+                continue;
+            }
+            let mut line = prefix.as_bytes().to_vec();
+            line.append(pending);
+            line.push(b'\n');
+            self.write(&line);
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        match &mut self.file {
+            // A transcript we can't write shouldn't take the bot down with it.
+            Some(file) => {
+                let _ = file.write_all(bytes);
+            }
+            None => self.buffer.extend_from_slice(bytes),
+        }
+    }
+}
+
+// Copies everything read through `reader` and written through `writer` into a
+// transcript. Like the log, lines are kept in memory until `open` is called, since the
+// file name depends on the player id the engine sends first.
+#[derive(Clone)]
+pub struct TranscriptRecorder {
+    recording: Rc<RefCell<Recording>>,
+}
+
+impl TranscriptRecorder {
+    pub fn new(seed: u64) -> TranscriptRecorder {
+        let recording = Recording {
+            file: None,
+            buffer: format!("{}{}\n", SEED, seed).into_bytes(),
+            pending_in: Vec::new(),
+            pending_out: Vec::new(),
+        };
+        TranscriptRecorder { recording: Rc::new(RefCell::new(recording)) }
+    }
+
+    pub fn open(&self, path: &str) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        let mut recording = self.recording.borrow_mut();
+        file.write_all(&recording.buffer)?;
+        recording.buffer.clear();
+        recording.file = Some(file);
+        Ok(())
+    }
+
+    pub fn reader(&self, inner: Box<dyn BufRead>) -> Box<dyn BufRead> {
+        Box::new(RecordingReader { inner, recorder: self.clone() })
+    }
+
+    pub fn writer(&self, inner: Box<dyn Write>) -> Box<dyn Write> {
+        Box::new(RecordingWriter { inner, recorder: self.clone() })
+    }
+}
+
+struct RecordingReader {
+    inner: Box<dyn BufRead>,
+    recorder: TranscriptRecorder,
+}
+
+impl Read for RecordingReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = {
+            let available = self.fill_buf()?;
+            let n = available.len().min(buf.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for RecordingReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    // Only what is consumed is recorded, so read-ahead never shows up early.
+    fn consume(&mut self, amt: usize) {
+        if let Ok(available) = self.inner.fill_buf() {
+            let end = amt.min(available.len());
+            self.recorder.recording.borrow_mut().record(FROM_ENGINE, &available[..end]);
+        }
+        self.inner.consume(amt);
+    }
+}
+
+struct RecordingWriter {
+    inner: Box<dyn Write>,
+    recorder: TranscriptRecorder,
+}
+
+impl Write for RecordingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.recorder.recording.borrow_mut().record(FROM_BOT, &buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(file) = &mut self.recorder.recording.borrow_mut().file {
+            let _ = file.flush();
+        }
+        self.inner.flush()
+    }
+}
+
+// A transcript read back, for playing the same game again offline.
+pub struct Transcript {
+    pub seed: Option<u64>,
+    pub from_engine: Vec<String>,
+    pub from_bot: Vec<String>,
+}
+
+impl Transcript {
+    pub fn load(path: &str) -> Result<Transcript, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("can't read transcript {}: {}", path, e))?;
+        let mut transcript = Transcript { seed: None, from_engine: Vec::new(), from_bot: Vec::new() };
+        for (i, line) in text.lines().enumerate() {
+            if let Some(seed) = line.strip_prefix(SEED) {
+                let seed = seed.trim().parse().map_err(|_| format!("{}:{}: bad seed", path, i + 1))?;
+                transcript.seed = Some(seed);
+            } else if let Some(line) = line.strip_prefix(FROM_ENGINE) {
+                transcript.from_engine.push(line.to_string());
+            } else if let Some(line) = line.strip_prefix(FROM_BOT) {
+                transcript.from_bot.push(line.to_string());
+            } else if !line.is_empty() {
+                return Err(format!("{}:{}: expected a line starting with '<' or '>'", path, i + 1));
+            }
+        }
+        Ok(transcript)
+    }
+
+    // What the engine sent, to be read by Game::from_io in place of stdin.
+    pub fn engine_input(&self) -> Box<dyn BufRead> {
+        let mut text = self.from_engine.join("\n");
+        text.push('\n');
+        Box::new(Cursor::new(text.into_bytes()))
+    }
+
+    // The first line where `replayed` differs from what the bot sent in the recorded
+    // game, as (turn, recorded, replayed). The first line is the bot's name, sent on
+    // turn 0; after that there is one line per turn.
+    pub fn first_difference(&self, replayed: &[String]) -> Option<(usize, String, String)> {
+        let turns = self.from_bot.len().max(replayed.len());
+        (0..turns).find_map(|turn| {
+            let recorded = self.from_bot.get(turn).cloned().unwrap_or_else(|| "<nothing>".to_string());
+            let replayed = replayed.get(turn).cloned().unwrap_or_else(|| "<nothing>".to_string());
+            if recorded == replayed {
+                None
+            } else {
+                Some((turn, recorded, replayed))
+            }
+        })
+    }
+}
+
+// Collects the lines the bot writes during a replay so they can be compared.
+#[derive(Clone, Default)]
+pub struct CapturedOutput {
+    bytes: Rc<RefCell<Vec<u8>>>,
+}
+
+impl CapturedOutput {
+    pub fn new() -> CapturedOutput {
+        CapturedOutput::default()
+    }
+
+    pub fn writer(&self) -> Box<dyn Write> {
+        Box::new(self.clone())
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        String::from_utf8_lossy(&self.bytes.borrow()).lines().map(|x| x.to_string()).collect()
+    }
+}
+
+impl Write for CapturedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bytes.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::transcript::Transcript;
+
+    fn transcript(from_bot: &[&str]) -> Transcript {
+        Transcript {
+            seed: Some(7),
+            from_engine: Vec::new(),
+            from_bot: from_bot.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn first_difference_finds_the_turn_that_diverges() {
+        let recorded = transcript(&["bot", "g", "m 0 n", "m 0 e m 1 s", "m 0 o"]);
+
+        assert!(recorded.first_difference(&lines(&["bot", "g", "m 0 n", "m 0 e m 1 s", "m 0 o"])).is_none());
+        assert_eq!(
+            recorded.first_difference(&lines(&["bot", "g", "m 0 n", "m 0 e m 1 n", "m 0 w"])),
+            Some((3, "m 0 e m 1 s".to_string(), "m 0 e m 1 n".to_string()))
+        );
+    }
+
+    #[test]
+    fn first_difference_reports_a_replay_that_stops_early_or_runs_on() {
+        let recorded = transcript(&["bot", "g", "m 0 n"]);
+
+        assert_eq!(
+            recorded.first_difference(&lines(&["bot", "g"])),
+            Some((2, "m 0 n".to_string(), "<nothing>".to_string()))
+        );
+        assert_eq!(
+            recorded.first_difference(&lines(&["bot", "g", "m 0 n", "g"])),
+            Some((3, "<nothing>".to_string(), "g".to_string()))
+        );
+    }
+}
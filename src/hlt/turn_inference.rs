@@ -0,0 +1,292 @@
+use hlt::command::Command;
+use hlt::direction::Direction;
+use hlt::game::Game;
+use hlt::game_event::GameEvent;
+use hlt::game_map::GameMap;
+use hlt::position::Position;
+use hlt::DropoffId;
+use hlt::PlayerId;
+use hlt::ShipId;
+use std::collections::HashMap;
+
+// What a ship most likely did between two frames.
+#[derive(Copy, Clone, PartialEq)]
+pub enum InferredAction {
+    Move(Direction),
+    // Stayed on its cell and picked up `mined`, counting any inspiration bonus. `stalled`
+    // ships couldn't have left: they had less cargo than the move would cost.
+    Stay { mined: usize, stalled: bool },
+    Construct(DropoffId),
+    // Lost in a collision at `position`, reached by moving in `direction`.
+    Destroyed { position: Position, direction: Direction },
+}
+
+pub struct InferredCollision {
+    pub position: Position,
+    // Ships that moved onto the cell, and the one that was sitting on it, if any.
+    pub movers: Vec<ShipId>,
+    pub stationary: Option<ShipId>,
+    pub owners: Vec<PlayerId>,
+    // Cargo left after paying for moves. It is left on the cell unless the cell is a
+    // structure, in which case it goes to the structure's owner and `dropped_on_map` is 0.
+    pub halite_dropped: usize,
+    pub dropped_on_map: usize,
+}
+
+impl InferredCollision {
+    pub fn ship_ids(&self) -> Vec<ShipId> {
+        let mut ship_ids = self.movers.clone();
+        ship_ids.extend(self.stationary);
+        ship_ids.sort_by_key(|id| id.0);
+        ship_ids
+    }
+
+    // Ships of one player running into each other.
+    pub fn is_self_collision(&self) -> bool {
+        self.owners.windows(2).all(|pair| pair[0] == pair[1])
+    }
+
+    // The ships that hit `ship_id`: everyone else if it was sitting still, only the
+    // ships that were sitting still if it moved.
+    pub fn hit_by(&self, ship_id: ShipId) -> Vec<ShipId> {
+        if self.stationary == Some(ship_id) {
+            self.movers.clone()
+        } else {
+            self.movers.iter().filter(|id| **id != ship_id).cloned().chain(self.stationary).collect()
+        }
+    }
+}
+
+// Every player's turn as far as it can be told from two consecutive frames, read off
+// the events GameEvent::derive finds between them. Ships that were built and destroyed on
+// the same turn never show up in a frame, so their spawns are missing from `spawned`.
+// Ships that stalled are taken to have stayed, whatever move they were given.
+pub struct TurnInference {
+    pub actions: HashMap<ShipId, InferredAction>,
+    pub collisions: Vec<InferredCollision>,
+    pub spawned: Vec<PlayerId>,
+    // Who each ship in `actions` belonged to when the turn started.
+    pub owners: HashMap<ShipId, PlayerId>,
+}
+
+impl TurnInference {
+    pub fn infer(previous: &Game, next: &Game) -> Result<TurnInference, String> {
+        TurnInference::from_events(previous, next, &GameEvent::derive(previous, next))
+    }
+
+    // For callers that already derived the events for these frames. Events that don't
+    // fit the frames, such as a collision out of a ship's reach, are an error.
+    pub fn from_events(previous: &Game, next: &Game, events: &[GameEvent]) -> Result<TurnInference, String> {
+        let mut inference = TurnInference {
+            actions: HashMap::new(),
+            collisions: Vec::new(),
+            spawned: Vec::new(),
+            owners: previous.ships.values().map(|ship| (ship.id, ship.owner)).collect(),
+        };
+
+        // Ships without an event of their own stayed put.
+        for (ship_id, before) in &previous.ships {
+            if let Some(after) = next.ships.get(ship_id) {
+                let mined = after.halite.saturating_sub(before.halite);
+                inference.actions.insert(*ship_id, InferredAction::Stay { mined, stalled: false });
+            }
+        }
+
+        for event in events {
+            match event {
+                GameEvent::ShipSpawned { owner, .. } => inference.spawned.push(*owner),
+                GameEvent::ShipMoved { ship_id, direction, .. } => {
+                    inference.actions.insert(*ship_id, InferredAction::Move(*direction));
+                }
+                GameEvent::ShipStalled { ship_id, .. } => {
+                    if let Some(InferredAction::Stay { stalled, .. }) = inference.actions.get_mut(ship_id) {
+                        *stalled = true;
+                    }
+                }
+                GameEvent::DropoffConstructed { dropoff_id, ship_id: Some(ship_id), .. } => {
+                    inference.actions.insert(*ship_id, InferredAction::Construct(*dropoff_id));
+                }
+                GameEvent::ShipsCollided { position, ship_ids, halite_dropped } => {
+                    for ship_id in ship_ids {
+                        let from = match previous.ships.get(ship_id) {
+                            Some(ship) => ship.position,
+                            None => return Err(format!("collision with unknown ship {}", ship_id.0)),
+                        };
+                        let direction = direction_between(&next.map, &from, position).ok_or_else(|| {
+                            format!(
+                                "ship {} at ({}, {}) can't reach the collision at ({}, {})",
+                                ship_id.0, from.x, from.y, position.x, position.y
+                            )
+                        })?;
+                        inference.actions.insert(*ship_id, InferredAction::Destroyed { position: *position, direction });
+                    }
+                    inference.collisions.push(TurnInference::collision(
+                        previous,
+                        next,
+                        *position,
+                        ship_ids,
+                        *halite_dropped,
+                    ));
+                }
+                _ => (),
+            }
+        }
+        inference.spawned.sort_by_key(|id| id.0);
+        Ok(inference)
+    }
+
+    fn collision(
+        previous: &Game,
+        next: &Game,
+        position: Position,
+        ship_ids: &[ShipId],
+        halite_dropped: usize,
+    ) -> InferredCollision {
+        let mut movers: Vec<ShipId> = Vec::new();
+        let mut stationary: Option<ShipId> = None;
+        let mut owners: Vec<PlayerId> = Vec::new();
+        for ship_id in ship_ids {
+            let ship = &previous.ships[ship_id];
+            if ship.position == position {
+                stationary = Some(*ship_id);
+            } else {
+                movers.push(*ship_id);
+            }
+            owners.push(ship.owner);
+        }
+        movers.sort_by_key(|id| id.0);
+
+        let on_structure = next.map.at_position(&position).structure.is_some();
+        InferredCollision {
+            position,
+            movers,
+            stationary,
+            owners,
+            halite_dropped,
+            dropped_on_map: if on_structure { 0 } else { halite_dropped },
+        }
+    }
+
+    // The commands the player most likely sent, in ship id order with the spawn last.
+    // Ships that stayed get an explicit "still" move.
+    pub fn commands(&self, player_id: PlayerId) -> Vec<Command> {
+        let mut ship_ids: Vec<&ShipId> =
+            self.actions.keys().filter(|id| self.owners.get(id) == Some(&player_id)).collect();
+        ship_ids.sort_by_key(|id| id.0);
+
+        let mut commands: Vec<Command> = ship_ids
+            .iter()
+            .map(|ship_id| match self.actions[ship_id] {
+                InferredAction::Move(direction) | InferredAction::Destroyed { direction, .. } => {
+                    Command::move_ship(**ship_id, direction)
+                }
+                InferredAction::Stay { .. } => Command::move_ship(**ship_id, Direction::Still),
+                InferredAction::Construct(_) => Command::transform_ship_into_dropoff_site(**ship_id),
+            })
+            .collect();
+        if self.spawned.contains(&player_id) {
+            commands.push(Command::spawn_ship());
+        }
+        commands
+    }
+}
+
+// None if `to` is neither `from` nor next to it.
+fn direction_between(map: &GameMap, from: &Position, to: &Position) -> Option<Direction> {
+    if map.normalize(from) == *to {
+        return Some(Direction::Still);
+    }
+    Direction::get_all_cardinals().into_iter().find(|d| map.normalize(&from.directional_offset(*d)) == *to)
+}
+
+#[cfg(test)]
+mod tests {
+    use hlt::command::Command;
+    use hlt::direction::Direction;
+    use hlt::game_event::GameEvent;
+    use hlt::test_game::*;
+    use hlt::turn_inference::InferredAction;
+    use hlt::turn_inference::TurnInference;
+    use hlt::PlayerId;
+    use hlt::ShipId;
+
+    const YARDS: [(i32, i32); 2] = [(2, 2), (13, 13)];
+
+    fn wire(commands: &[Command]) -> Vec<String> {
+        commands.iter().map(|command| command.to_string()).collect()
+    }
+
+    #[test]
+    fn commands_are_the_ones_that_were_sent() {
+        let mut game = game(16, &YARDS);
+        add_ship(&mut game, 0, 0, 5, 5, 50);
+        add_ship(&mut game, 0, 1, 8, 8, 0);
+        add_ship(&mut game, 0, 2, 10, 4, 300);
+        add_ship(&mut game, 1, 3, 3, 10, 20);
+        add_ship(&mut game, 1, 4, 11, 11, 0);
+        add_ship(&mut game, 1, 5, 6, 12, 40);
+        add_ship(&mut game, 0, 6, 5, 12, 70);
+
+        // In the order commands() gives them: ship id order, every ship, the spawn last.
+        let sent = vec![
+            vec![
+                Command::Move(ShipId(0), Direction::North),
+                Command::Move(ShipId(1), Direction::Still),
+                Command::Construct(ShipId(2)),
+                Command::Move(ShipId(6), Direction::Still),
+                Command::Spawn,
+            ],
+            vec![
+                Command::Move(ShipId(3), Direction::East),
+                Command::Move(ShipId(4), Direction::Still),
+                Command::Move(ShipId(5), Direction::West),
+            ],
+        ];
+        let (previous, result) = step(&mut game, &sent);
+        assert!(result.errors.is_empty());
+        assert_eq!(result.collisions.len(), 1);
+
+        let events = GameEvent::derive(&previous, &game);
+        let inference = TurnInference::from_events(&previous, &game, &events).unwrap();
+        assert_eq!(wire(&inference.commands(PlayerId(0))), wire(&sent[0]));
+        assert_eq!(wire(&inference.commands(PlayerId(1))), wire(&sent[1]));
+
+        assert_eq!(inference.collisions.len(), 1);
+        let collision = &inference.collisions[0];
+        assert!(collision.position == pos(5, 12));
+        assert!(collision.movers == vec![ShipId(5)]);
+        assert!(collision.stationary == Some(ShipId(6)));
+        assert_eq!(collision.halite_dropped, 30 + 70);
+        assert!(!collision.is_self_collision());
+        assert!(collision.hit_by(ShipId(6)) == vec![ShipId(5)]);
+    }
+
+    #[test]
+    fn a_stalled_ship_is_taken_to_have_stayed() {
+        let mut game = game(16, &YARDS);
+        add_ship(&mut game, 0, 0, 5, 5, 5);
+        let (previous, _) = step(&mut game, &[vec![Command::Move(ShipId(0), Direction::East)], vec![]]);
+
+        let inference = TurnInference::infer(&previous, &game).unwrap();
+        assert!(inference.actions[&ShipId(0)] == InferredAction::Stay { mined: 25, stalled: true });
+        assert_eq!(wire(&inference.commands(PlayerId(0))), vec!["m 0 o"]);
+    }
+
+    #[test]
+    fn a_collision_out_of_reach_is_an_error() {
+        let mut game = game(16, &YARDS);
+        add_ship(&mut game, 0, 0, 3, 4, 100);
+        add_ship(&mut game, 1, 1, 4, 5, 50);
+        let commands = vec![vec![Command::Move(ShipId(0), Direction::South)], vec![Command::Move(ShipId(1), Direction::West)]];
+        let (previous, _) = step(&mut game, &commands);
+
+        let far = vec![GameEvent::ShipsCollided { position: pos(9, 9), ship_ids: vec![ShipId(0), ShipId(1)], halite_dropped: 130 }];
+        assert_eq!(
+            TurnInference::from_events(&previous, &game, &far).err(),
+            Some("ship 0 at (3, 4) can't reach the collision at (9, 9)".to_string())
+        );
+
+        let unknown = vec![GameEvent::ShipsCollided { position: pos(3, 5), ship_ids: vec![ShipId(7)], halite_dropped: 0 }];
+        assert!(TurnInference::from_events(&previous, &game, &unknown).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+use hlt::arena::Arena;
+use hlt::baseline;
+use hlt::bot::Bot;
+use hlt::command::Command;
+use hlt::game::Game;
+use hlt::position::Position;
+use hlt::replay_reader::Replay;
+use hlt::snapshot::Snapshot;
+use hlt::strategy::Strategy;
+use hlt::PlayerId;
+use hlt::ShipId;
+
+struct RecordedTurn {
+    ships: Vec<(ShipId, Position, usize)>,
+    halite: usize,
+    commands: Vec<Command>,
+}
+
+fn player_state(game: &Game, player_id: PlayerId) -> (Vec<(ShipId, Position, usize)>, usize) {
+    let mut ships: Vec<(ShipId, Position, usize)> = game.players[player_id.0]
+        .ship_ids
+        .iter()
+        .map(|id| (*id, game.ships[id].position, game.ships[id].halite))
+        .collect();
+    ships.sort_by_key(|x| (x.0).0);
+    (ships, game.players[player_id.0].halite)
+}
+
+// Sends what an opponent sent in the replay for as long as its ships and bank match the
+// recorded frame, then hands over to a baseline for the rest of the game, since the
+// recorded commands no longer mean anything once the game went somewhere else.
+pub struct ReplayedOpponent {
+    player_id: PlayerId,
+    turns: Vec<RecordedTurn>,
+    fallback: Box<dyn Strategy>,
+    pub diverged_at: Option<usize>,
+}
+
+impl ReplayedOpponent {
+    pub fn new(replay: &Replay, player_id: PlayerId, fallback: Box<dyn Strategy>) -> ReplayedOpponent {
+        let turns = replay
+            .frames
+            .iter()
+            .map(|frame| {
+                let (ships, halite) = player_state(&frame.game, player_id);
+                RecordedTurn { ships, halite, commands: frame.commands[player_id.0].clone() }
+            })
+            .collect();
+        ReplayedOpponent { player_id, turns, fallback, diverged_at: None }
+    }
+}
+
+impl Strategy for ReplayedOpponent {
+    fn name(&self) -> String {
+        format!("replayed player {}", self.player_id.0)
+    }
+
+    fn play_turn(&mut self, game: &Game) -> Vec<Command> {
+        if self.diverged_at.is_none() {
+            let (ships, halite) = player_state(game, self.player_id);
+            let matches = match self.turns.get(game.turn_number) {
+                Some(turn) => ships == turn.ships && halite == turn.halite,
+                None => false,
+            };
+            if matches {
+                return self.turns[game.turn_number].commands.clone();
+            }
+            self.diverged_at = Some(game.turn_number);
+        }
+        self.fallback.play_turn(game)
+    }
+}
+
+pub struct WhatIfResult {
+    pub start_turn: usize,
+    pub final_halite: Vec<usize>,
+    pub recorded_halite: Vec<usize>,
+    pub diverged_at: Vec<Option<usize>>,
+}
+
+// Replays a game from the start of `turn` with the current Bot playing `player_id` and
+// everybody else replayed, falling back to the named baseline.
+pub fn run(replay: &Replay, turn: usize, player_id: PlayerId, fallback: &str) -> Result<WhatIfResult, String> {
+    // The snapshot carries the ids the engine hands out next, so new ships get the ids
+    // they had in the replay and the recorded commands still refer to them.
+    let snapshot = Snapshot::from_replay(replay, turn)?;
+    let num_players = snapshot.game.players.len();
+    if player_id.0 >= num_players {
+        return Err(format!("there is no player {} in this replay", player_id.0));
+    }
+
+    let mut arena = Arena::from_snapshot(snapshot);
+    let start_turn = arena.game.turn_number;
+
+    let mut bot = Bot::new(&arena.view(player_id), replay.seed.wrapping_add(player_id.0 as u64));
+    let mut opponents: Vec<Option<ReplayedOpponent>> = Vec::new();
+    for i in 0..num_players {
+        if i == player_id.0 {
+            opponents.push(None);
+            continue;
+        }
+        let view = arena.view(PlayerId(i));
+        let fallback = baseline::by_name(fallback, &view, replay.seed.wrapping_add(i as u64))
+            .ok_or_else(|| format!("unknown baseline {}", fallback))?;
+        opponents.push(Some(ReplayedOpponent::new(replay, PlayerId(i), fallback)));
+    }
+
+    while !arena.is_over() {
+        let commands: Vec<Vec<Command>> = opponents
+            .iter_mut()
+            .enumerate()
+            .map(|(i, opponent)| {
+                let view = arena.view(PlayerId(i));
+                match opponent {
+                    Some(opponent) => opponent.play_turn(&view),
+                    None => bot.play_turn(&view),
+                }
+            })
+            .collect();
+        arena.resolve(&commands);
+    }
+
+    Ok(WhatIfResult {
+        start_turn,
+        final_halite: arena.game.players.iter().map(|player| player.halite).collect(),
+        recorded_halite: replay.final_halite.clone(),
+        diverged_at: opponents.iter().map(|x| x.as_ref().and_then(|x| x.diverged_at)).collect(),
+    })
+}
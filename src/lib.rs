@@ -0,0 +1,5 @@
+#[macro_use]
+extern crate lazy_static;
+extern crate rand;
+
+pub mod hlt;
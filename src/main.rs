@@ -1,137 +1,133 @@
-#[macro_use]
-extern crate lazy_static;
-extern crate rand;
+extern crate my_bot;
 
-use hlt::command::Command;
+use my_bot::hlt;
+use hlt::bot;
+use hlt::bot::Bot;
 use hlt::game::Game;
-use hlt::gradient_map::GradientMap;
+use hlt::game_event::EventLog;
 use hlt::log::Log;
-use hlt::navi::Navi;
-use hlt::ShipId;
+use hlt::protocol_error::ProtocolError;
+use hlt::strategy::Strategy;
+use hlt::transcript::CapturedOutput;
+use hlt::transcript::Transcript;
+use hlt::transcript::TranscriptRecorder;
 use std::env;
+use std::process::exit;
 use std::time::Instant;
 use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
 
-mod hlt;
-
+// Usage: my_bot [SEED] [--events] [--record]
+//        my_bot --replay-transcript PATH
+// --events writes what happened each turn to bot-N.events and --record keeps the whole
+// protocol exchange in bot-N.transcript. Both are off by default so a ladder submission
+// writes nothing but its log.
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let rng_seed: u64 = if args.len() > 1 {
-        args[1].parse().unwrap()
-    } else {
-        SystemTime::now()
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() > 1 && args[0] == "--replay-transcript" {
+        replay_transcript(&args[1]);
+        return;
+    }
+
+    let mut write_events = false;
+    let mut record = false;
+    let mut seed_arg: Option<&String> = None;
+    for arg in &args {
+        match arg.as_str() {
+            "--events" => write_events = true,
+            "--record" => record = true,
+            _ if arg.starts_with("--") => fail(&format!("unknown option {}", arg)),
+            _ => seed_arg = Some(arg),
+        }
+    }
+
+    let rng_seed: u64 = match seed_arg {
+        Some(seed) => seed.parse().unwrap_or_else(|_| fail(&format!("bad seed '{}'", seed))),
+        None => SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
-            .as_secs()
+            .as_secs(),
     };
 
-    let mut game = Game::new();
-    let mut navi = Navi::new(game.map.width, game.map.height, &game);
+    let game = if record {
+        Game::new_recorded(&TranscriptRecorder::new(rng_seed))
+    } else {
+        Game::new()
+    };
+    let game = game.unwrap_or_else(|e| e.exit());
+    let event_log = if write_events { EventLog::open(game.my_id).ok() } else { None };
+    play(game, rng_seed, event_log).exit();
+}
 
-    let player_count = game.players.len();
+// Plays until the input ends or can't be read, and returns why it stopped.
+fn play(mut game: Game, rng_seed: u64, mut event_log: Option<EventLog>) -> ProtocolError {
+    let mut bot = Bot::new(&game, rng_seed);
 
-    //ignore number am bad at remembering to update version
-    Game::ready("mellow root v20");
+    game.ready(bot::NAME);
+    Log::log(&format!("rng seed: {}", rng_seed));
+    for key in game.constants.extra_keys() {
+        Log::log(&format!("constant {}: {}", key, game.constants.get(key).unwrap()));
+    }
 
     loop {
         let now = Instant::now();
-        game.update_frame();
-
-        let mut gradient_map = GradientMap::construct(&game);
-        gradient_map.initialize(&game, &navi);
-        navi.update_frame(&game, &gradient_map);
-
-        let me = &game.players[game.my_id.0];
-
-        let mut command_queue: Vec<Command> = Vec::new();
-        let mut command_order: Vec<ShipId> = Vec::new();
-
-        command_order.append(&mut navi.are_stalled);
-        command_order.append(&mut navi.at_dropoff);
-        for (d, ship_ids) in &mut navi.coming_home {
-            if d == &(1 as usize) {
-                let mut new_vec = ship_ids.clone();
-                let mut ship_ids = Navi::sort_adjacent_dropoff(new_vec, &gradient_map, &game);
-                let adj_ships: Vec<usize> = ship_ids.iter().map(|x| x.0).collect();
-                command_order.append(&mut ship_ids);
-            } else {
-                command_order.append(ship_ids);
+        if let Err(error) = game.update_frame() {
+            for line in bot.scoreboard.summary() {
+                Log::log(&format!("scoreboard: {}", line));
             }
+            return error;
         }
 
-        let mut i = game.map.width;
-        while i > 0 {
-            if navi.gathering.contains_key(&i) {
-                let new_vec = navi.gathering.get_mut(&i).unwrap();
-                command_order.append(new_vec);
+        let command_queue = bot.play_turn(&game);
+        if let Some(event_log) = &mut event_log {
+            let mut written = event_log.write(game.turn_number - 1, &bot.events);
+            if let Some(inference) = &bot.inference {
+                for enemy in game.enemy_players() {
+                    let commands = inference.commands(enemy.id);
+                    written = written.and_then(|_| event_log.write_commands(game.turn_number - 1, enemy.id, &commands));
+                }
             }
-            i -= 1;
-        }
-
-        navi.clear();
-
-        let command_log: Vec<usize> = command_order.iter().map(|x| x.0).collect();
-
-        for ship_id in &command_order {
-            // once you fix colissions remove this
-            if game.ships.contains_key(ship_id) {
-                let ship = &game.ships[ship_id];
-                let command = navi.suggest_move(&mut gradient_map, &ship, &game);
-                navi.process_move(*ship_id);
-                command_queue.push(command);
+            if written.is_err() {
+                Log::log("could not write the event log");
             }
         }
+        game.expect_next_frame(&command_queue);
+        game.end_turn(&command_queue);
+        Log::log(&format!(
+            "seconds: {}",
+            now.elapsed().as_secs() as f64 + now.elapsed().subsec_nanos() as f64 * 1e-9
+        ));
+    }
+}
 
-        for ship_id in &me.ship_ids {
-            if !navi.have_moved.contains_key(ship_id) {
-                navi.update_for_new_ship(*ship_id);
-
-                let ship = &game.ships[ship_id];
-                let command = navi.suggest_move(&mut gradient_map, &ship, &game);
-                navi.process_move(*ship_id);
-                command_queue.push(command);
-            }
-        }
+// Plays a recorded game again with this build and reports the first turn where its
+// commands differ from the recorded ones.
+fn replay_transcript(path: &str) {
+    let transcript = Transcript::load(path).unwrap_or_else(|e| fail(&e));
+    let rng_seed = transcript.seed.unwrap_or_else(|| fail(&format!("{} has no seed line", path)));
 
-        let mut saving_for_d_off = 0;
-        if navi.this_turn_dropoff {
-            saving_for_d_off = game.constants.dropoff_cost;
-        }
+    // Nothing would ever open the log, so it would only pile up in memory.
+    Log::mute();
 
-        let mut production = 2000;
-        if command_queue.len() > 0 {
-            production = game.map.total_halite / game.players.len() / command_queue.len();
-        }
+    let output = CapturedOutput::new();
+    let game = Game::from_io(transcript.engine_input(), output.writer()).unwrap_or_else(|e| fail(&e.to_string()));
+    let error = play(game, rng_seed, None);
+    if error != ProtocolError::Closed {
+        println!("replay stopped: {}", error);
+    }
 
-        if player_count == 2 {
-            if me.halite >= game.constants.ship_cost + saving_for_d_off
-                && !gradient_map.at_position(&me.shipyard.position).my_occupy
-                && (game.ships.len() - me.ship_ids.len() + 1 > me.ship_ids.len()
-                    && game.constants.max_turns - game.turn_number > 100)
-            {
-                command_queue.push(me.shipyard.spawn());
-            }
-        } else {
-            if (production > 1500
-                || Game::half_halite_collected(
-                    &game.map.total_halite,
-                    &gradient_map.halite_remaining,
-                ))
-                && me.halite >= game.constants.ship_cost + saving_for_d_off
-                && !gradient_map.at_position(&me.shipyard.position).my_occupy
-                && game.constants.max_turns - game.turn_number > 200
-            {
-                command_queue.push(me.shipyard.spawn());
-            }
+    match transcript.first_difference(&output.lines()) {
+        Some((turn, recorded, replayed)) => {
+            println!("turn {}: commands differ", turn);
+            println!("  recorded: {}", recorded);
+            println!("  replayed: {}", replayed);
+            exit(1);
         }
-
-        navi.end_turn();
-        command_order.clear();
-        Game::end_turn(&command_queue);
-        Log::log(&format!(
-            "seconds: {}",
-            now.elapsed().as_secs() as f64 + now.elapsed().subsec_nanos() as f64 * 1e-9
-        ));
+        None => println!("no differences over {} turns", transcript.from_bot.len().saturating_sub(1)),
     }
 }
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    exit(1);
+}